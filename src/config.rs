@@ -1,12 +1,19 @@
 use std::{
+    collections::HashMap,
     fs,
-    path::{Path, PathBuf},
+    path::{Component, Path, PathBuf},
+    sync::{Arc, Mutex},
 };
 
-use anyhow::{Context, Result};
-use serde::Deserialize;
+use anyhow::{Context, Result, bail};
+use globset::{GlobBuilder, GlobSet, GlobSetBuilder};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use toml_edit::{ImDocument, TableLike};
 
-#[derive(Debug, Clone, Deserialize)]
+pub const CONFIG_FILE_NAME: &str = "sv-fmt.toml";
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
 #[serde(default)]
 pub struct FormatConfig {
     pub indent_width: usize,
@@ -16,9 +23,139 @@ pub struct FormatConfig {
     pub inline_end_else: bool,
     pub space_after_comma: bool,
     pub remove_call_space: bool,
+    pub space_around_operators: bool,
     pub max_line_length: usize,
     pub align_case_colon: bool,
     pub auto_wrap_long_lines: bool,
+    pub continuation_indent: usize,
+    pub align_to_open_paren: bool,
+    pub port_list_per_line: bool,
+    pub port_list_leading_commas: bool,
+    pub align_port_list: bool,
+    pub param_list_max_entries: usize,
+    pub align_parameters: bool,
+    pub connections_per_line: bool,
+    pub align_connections: bool,
+    pub align_assignments: bool,
+    pub align_declarations: bool,
+    pub declaration_max_padding: usize,
+    pub align_trailing_comments: bool,
+    pub snap_trailing_comments: bool,
+    pub keyword_case: KeywordCase,
+    pub number_base_case: LetterCase,
+    pub number_digit_case: LetterCase,
+    pub defines: Vec<String>,
+    pub include_dirs: Vec<PathBuf>,
+    pub fallback_on_parse_error: bool,
+    pub exclude: Vec<String>,
+    pub respect_gitignore: bool,
+    pub root: bool,
+    pub allow_unknown_keys: bool,
+    pub overrides: Vec<ConfigOverride>,
+}
+
+/// How `keyword_case` rewrites keywords.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum KeywordCase {
+    Preserve,
+    Lower,
+}
+
+/// How a number case option rewrites letters.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LetterCase {
+    Preserve,
+    Lower,
+    Upper,
+}
+
+/// Keys that configure the run as a whole and so cannot be set by an `[[overrides]]` entry.
+const RUN_KEYS: &[&str] = &["root", "exclude", "respect_gitignore", "allow_unknown_keys"];
+
+/// An `[[overrides]]` entry: keys that replace the enclosing configuration for matching files.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct ConfigOverride {
+    pub files: Vec<String>,
+    #[serde(flatten)]
+    pub settings: toml::Table,
+    #[serde(skip)]
+    base_dir: PathBuf,
+    #[serde(skip)]
+    matcher: Option<GlobSet>,
+}
+
+impl ConfigOverride {
+    fn compile(&mut self, config_path: &Path) -> Result<()> {
+        if self.files.is_empty() {
+            bail!(
+                "[[overrides]] entry in {} has no `files` patterns",
+                config_path.display()
+            );
+        }
+        if self.settings.contains_key("overrides") {
+            bail!("[[overrides]] entries in {} cannot be nested", config_path.display());
+        }
+        if let Some(key) = RUN_KEYS.iter().find(|key| self.settings.contains_key(**key)) {
+            bail!(
+                "`{key}` in [[overrides]] in {} applies to the whole run and cannot be set per file",
+                config_path.display()
+            );
+        }
+        let mut builder = GlobSetBuilder::new();
+        for pattern in &self.files {
+            let pattern = if pattern.contains('/') {
+                pattern.trim_start_matches('/').to_string()
+            } else {
+                format!("**/{pattern}")
+            };
+            let glob = GlobBuilder::new(&pattern)
+                .literal_separator(true)
+                .build()
+                .with_context(|| format!("invalid override pattern `{pattern}` in {}", config_path.display()))?;
+            builder.add(glob);
+        }
+        self.matcher = Some(builder.build()?);
+        self.base_dir = std::path::absolute(config_path)?
+            .parent()
+            .map(Path::to_path_buf)
+            .unwrap_or_default();
+
+        FormatConfig::deserialize(toml::Value::Table(self.settings.clone()))
+            .with_context(|| format!("invalid [[overrides]] entry in {}", config_path.display()))?;
+        Ok(())
+    }
+
+    fn matches(&self, file: &Path) -> bool {
+        let (Some(matcher), Ok(file)) = (&self.matcher, std::path::absolute(file)) else {
+            return false;
+        };
+        file.strip_prefix(&self.base_dir)
+            .is_ok_and(|relative| matcher.is_match(relative))
+    }
+}
+
+impl FormatConfig {
+    /// Returns the configuration for `file` with every matching `[[overrides]]` entry applied.
+    pub fn for_file(&self, file: &Path) -> Result<FormatConfig> {
+        let mut matching = self.overrides.iter().filter(|entry| entry.matches(file)).peekable();
+        if matching.peek().is_none() {
+            return Ok(FormatConfig {
+                overrides: Vec::new(),
+                ..self.clone()
+            });
+        }
+        let mut table = toml::Table::try_from(self).context("failed to serialize configuration")?;
+        table.remove("overrides");
+        for entry in matching {
+            table.extend(entry.settings.clone());
+        }
+        let mut config = FormatConfig::deserialize(toml::Value::Table(table))
+            .with_context(|| format!("invalid override for {}", file.display()))?;
+        sanitize(&mut config);
+        Ok(config)
+    }
 }
 
 impl Default for FormatConfig {
@@ -31,36 +168,452 @@ impl Default for FormatConfig {
             inline_end_else: true,
             space_after_comma: true,
             remove_call_space: true,
+            space_around_operators: true,
             max_line_length: 100,
             align_case_colon: true,
             auto_wrap_long_lines: false,
+            continuation_indent: 1,
+            align_to_open_paren: false,
+            port_list_per_line: false,
+            port_list_leading_commas: false,
+            align_port_list: true,
+            param_list_max_entries: 0,
+            align_parameters: true,
+            connections_per_line: false,
+            align_connections: true,
+            align_assignments: false,
+            align_declarations: false,
+            declaration_max_padding: 16,
+            align_trailing_comments: false,
+            snap_trailing_comments: false,
+            keyword_case: KeywordCase::Preserve,
+            number_base_case: LetterCase::Preserve,
+            number_digit_case: LetterCase::Preserve,
+            defines: Vec::new(),
+            include_dirs: Vec::new(),
+            fallback_on_parse_error: true,
+            exclude: Vec::new(),
+            respect_gitignore: false,
+            root: false,
+            allow_unknown_keys: false,
+            overrides: Vec::new(),
+        }
+    }
+}
+
+/// Description of every config key, written above it by [`config_template`].
+const KEY_DOCS: &[(&str, &str)] = &[
+    (
+        "indent_width",
+        "Number of spaces per indent level when use_tabs = false.",
+    ),
+    ("use_tabs", "Use tabs instead of spaces for indentation."),
+    (
+        "align_preprocessor",
+        "Left-align preprocessor directives regardless of indent level.",
+    ),
+    (
+        "wrap_multiline_blocks",
+        "Automatically wrap multiline if/else/loop bodies with begin...end.",
+    ),
+    ("inline_end_else", "Keep `end else` on the same line when possible."),
+    ("space_after_comma", "Force a space after each comma."),
+    (
+        "remove_call_space",
+        "Remove spaces between subroutine names and the opening parenthesis.",
+    ),
+    (
+        "space_around_operators",
+        "One space around binary and assignment operators, none after unary ones (!a, -b, i++, pkg::x, ##1).",
+    ),
+    (
+        "max_line_length",
+        "Maximum allowed line length when running with --check (0 disables the check).",
+    ),
+    (
+        "align_case_colon",
+        "Align `case`/`casez`/`casex` item colons within the same statement.",
+    ),
+    (
+        "auto_wrap_long_lines",
+        "Attempt to wrap long lines automatically (experimental).",
+    ),
+    (
+        "continuation_indent",
+        "Extra indent levels for lines continuing a statement or an unclosed (, [ or {.",
+    ),
+    (
+        "align_to_open_paren",
+        "Align lines inside brackets with the first token after the opening bracket instead.",
+    ),
+    (
+        "port_list_per_line",
+        "Put each port of an ANSI port list (module m(input a, ...)) on its own line.",
+    ),
+    (
+        "port_list_leading_commas",
+        "Start each line of a port, parameter or connection list laid out one per line with its comma.",
+    ),
+    (
+        "align_port_list",
+        "With port_list_per_line, align direction, type, dimensions, names and comments.",
+    ),
+    (
+        "param_list_max_entries",
+        "Put #(...) parameter lists longer than max_line_length, or with more entries than this (0: off), one per line.",
+    ),
+    (
+        "align_parameters",
+        "Align keyword, type, name and = of parameters in broken #(...) lists and consecutive declarations.",
+    ),
+    (
+        "connections_per_line",
+        "Put each named connection of an instance (.clk(clk), #(.W(8))) on its own line.",
+    ),
+    (
+        "align_connections",
+        "With connections_per_line, align the ( after each .name and the trailing comments.",
+    ),
+    (
+        "align_assignments",
+        "Line up the = or <= of consecutive one-line assignments not separated by comments.",
+    ),
+    (
+        "align_declarations",
+        "Align type, dimensions, name, initializer and // comment of consecutive one-line declarations.",
+    ),
+    (
+        "declaration_max_padding",
+        "With align_declarations, split a group rather than pad any column by more spaces than this.",
+    ),
+    (
+        "align_trailing_comments",
+        "Line up // comments ending consecutive lines of code (one space if that exceeds max_line_length).",
+    ),
+    (
+        "snap_trailing_comments",
+        "With align_trailing_comments, round the comment column up to a multiple of indent_width.",
+    ),
+    (
+        "keyword_case",
+        "\"lower\" or \"preserve\". Lowercase keywords, including MODULE/BEGIN in code that only parses then.",
+    ),
+    (
+        "number_base_case",
+        "\"lower\", \"upper\" or \"preserve\". Case of the base letter of literals like 8'hFF.",
+    ),
+    (
+        "number_digit_case",
+        "\"lower\", \"upper\" or \"preserve\". Case of the digits, x and z of based literals.",
+    ),
+    (
+        "defines",
+        "Macros predefined when parsing, as \"NAME\" or \"NAME=VALUE\" (like -D).",
+    ),
+    (
+        "include_dirs",
+        "Directories (relative to this file) searched for `include files (like -I).",
+    ),
+    (
+        "fallback_on_parse_error",
+        "Only fix indentation and trailing whitespace in code that fails to parse (--check still fails).",
+    ),
+    (
+        "exclude",
+        "Gitignore-style patterns (relative to this file) skipped when walking directories.",
+    ),
+    (
+        "respect_gitignore",
+        "Also skip files ignored by .gitignore when walking directories.",
+    ),
+    (
+        "root",
+        "Stop config discovery here instead of inheriting from parent directories.",
+    ),
+    (
+        "allow_unknown_keys",
+        "Ignore keys this version does not recognize instead of reporting them as errors.",
+    ),
+];
+
+/// Renders a commented `sv-fmt.toml` listing every key with its default value.
+pub fn config_template() -> String {
+    let defaults = toml::Table::try_from(FormatConfig::default()).expect("default config serializes");
+    let mut out = String::from(
+        "# Example configuration file for sv-fmt. Copy this file to sv-fmt.toml and\n\
+         # tweak the values to fit your project.\n",
+    );
+    for (key, value) in defaults.iter().filter(|(key, _)| *key != "overrides") {
+        out.push('\n');
+        if let Some(doc) = key_doc(key) {
+            out.push_str(&format!("# {doc}\n"));
         }
+        out.push_str(&format!("{key} = {value}\n"));
     }
+    out.push_str(
+        "\n# Per-glob overrides (patterns are relative to this file). Any key above can be overridden\n\
+         # except root, exclude, respect_gitignore and allow_unknown_keys.\n\
+         # [[overrides]]\n\
+         # files = [\"tb/**/*.sv\"]\n\
+         # indent_width = 4\n",
+    );
+    out
+}
+
+/// Description of `key` from [`KEY_DOCS`].
+pub fn key_doc(key: &str) -> Option<&'static str> {
+    KEY_DOCS
+        .iter()
+        .find(|(documented, _)| *documented == key)
+        .map(|(_, doc)| *doc)
 }
 
 pub fn load_config(path: Option<&Path>) -> Result<FormatConfig> {
     if let Some(path) = path {
         return read_config_file(path);
     }
+    let cwd = std::env::current_dir().context("failed to resolve the current directory")?;
+    load_config_chain(&config_chain(&cwd)?)
+}
+
+/// Resolves the configuration for each input file, caching the result per directory.
+pub struct ConfigResolver {
+    explicit: Option<(PathBuf, Arc<FormatConfig>)>,
+    cwd: PathBuf,
+    cache: Mutex<HashMap<PathBuf, Arc<FormatConfig>>>,
+}
+
+impl ConfigResolver {
+    pub fn new(explicit: Option<&Path>) -> Result<Self> {
+        let explicit = match explicit {
+            Some(path) => Some((path.to_path_buf(), Arc::new(read_config_file(path)?))),
+            None => None,
+        };
+        let cwd = std::env::current_dir().context("failed to resolve the current directory")?;
+        Ok(Self {
+            explicit,
+            cwd,
+            cache: Mutex::new(HashMap::new()),
+        })
+    }
 
-    let default_path = PathBuf::from("sv-fmt.toml");
-    if default_path.exists() {
-        return read_config_file(&default_path);
+    /// Returns the configuration that applies to `file`, including `[[overrides]]`.
+    pub fn resolve(&self, file: &Path) -> Result<Arc<FormatConfig>> {
+        let dir = file.parent().unwrap_or_else(|| Path::new(""));
+        let config = self.resolve_dir(dir)?;
+        if config.overrides.is_empty() {
+            return Ok(config);
+        }
+        Ok(Arc::new(config.for_file(&self.absolute(file))?))
     }
 
-    Ok(FormatConfig::default())
+    pub fn resolve_dir(&self, dir: &Path) -> Result<Arc<FormatConfig>> {
+        if let Some((_, config)) = &self.explicit {
+            return Ok(Arc::clone(config));
+        }
+        let dir = self.absolute(dir);
+        if let Some(config) = self.cache.lock().expect("config cache poisoned").get(&dir) {
+            return Ok(Arc::clone(config));
+        }
+        let config = Arc::new(load_config_chain(&config_chain(&dir)?)?);
+        self.cache
+            .lock()
+            .expect("config cache poisoned")
+            .insert(dir, Arc::clone(&config));
+        Ok(config)
+    }
+
+    pub fn chain_for_dir(&self, dir: &Path) -> Result<Vec<PathBuf>> {
+        match &self.explicit {
+            Some((path, _)) => Ok(vec![path.clone()]),
+            None => config_chain(&self.absolute(dir)),
+        }
+    }
+
+    fn absolute(&self, path: &Path) -> PathBuf {
+        self.cwd
+            .join(path)
+            .components()
+            .filter(|component| !matches!(component, Component::CurDir))
+            .collect()
+    }
+}
+
+/// Collects the `sv-fmt.toml` files that apply to `dir`, outermost first.
+pub fn config_chain(dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut chain = Vec::new();
+    for ancestor in dir.ancestors() {
+        let candidate = ancestor.join(CONFIG_FILE_NAME);
+        if candidate.is_file() {
+            let (config, _) = read_config_source(&candidate)?;
+            let is_root = config.root;
+            chain.push(candidate);
+            if is_root {
+                break;
+            }
+        }
+        if ancestor.join(".git").exists() {
+            break;
+        }
+    }
+    chain.reverse();
+    Ok(chain)
+}
+
+pub fn load_config_chain(chain: &[PathBuf]) -> Result<FormatConfig> {
+    let mut merged = toml::Table::new();
+    let mut overrides = Vec::new();
+    for path in chain {
+        let (config, mut table) = read_config_source(path)?;
+        table.remove("overrides");
+        merged.extend(table);
+        overrides.extend(config.overrides);
+    }
+    let mut config = FormatConfig::deserialize(toml::Value::Table(merged)).context("invalid merged config")?;
+    config.overrides = overrides;
+    sanitize(&mut config);
+    Ok(config)
 }
 
 fn read_config_file(path: &Path) -> Result<FormatConfig> {
+    let (mut config, _) = read_config_source(path)?;
+    sanitize(&mut config);
+    Ok(config)
+}
+
+/// Reads a config file as a typed config and as a table of the keys it sets.
+fn read_config_source(path: &Path) -> Result<(FormatConfig, toml::Table)> {
     let contents =
         fs::read_to_string(path).with_context(|| format!("failed to read config file {}", path.display()))?;
     let mut config: FormatConfig =
         toml::from_str(&contents).with_context(|| format!("invalid config file {}", path.display()))?;
+    if !config.allow_unknown_keys {
+        check_unknown_keys(path, &contents)?;
+    }
+    let mut table = contents
+        .parse::<toml::Table>()
+        .with_context(|| format!("invalid config file {}", path.display()))?;
+
+    // Include directories are relative to the config file, not to the working directory.
+    let base = std::path::absolute(path)?
+        .parent()
+        .map(Path::to_path_buf)
+        .unwrap_or_default();
+    for dir in &mut config.include_dirs {
+        *dir = base.join(&*dir);
+    }
+    anchor_include_dirs(&mut table, &base);
+    for entry in &mut config.overrides {
+        anchor_include_dirs(&mut entry.settings, &base);
+        entry.compile(path)?;
+    }
+    Ok((config, table))
+}
 
+fn anchor_include_dirs(table: &mut toml::Table, base: &Path) {
+    let Some(toml::Value::Array(dirs)) = table.get_mut("include_dirs") else {
+        return;
+    };
+    for dir in dirs {
+        if let toml::Value::String(dir) = dir {
+            *dir = base.join(&*dir).to_string_lossy().into_owned();
+        }
+    }
+}
+
+fn sanitize(config: &mut FormatConfig) {
     // Guard against invalid zero widths so formatter never panics later.
     if config.indent_width == 0 {
         config.indent_width = 2;
     }
+}
 
-    Ok(config)
+/// Every key `FormatConfig` accepts.
+static KNOWN_KEYS: Lazy<Vec<String>> = Lazy::new(|| {
+    toml::Table::try_from(FormatConfig::default())
+        .map(|table| table.keys().cloned().collect())
+        .unwrap_or_default()
+});
+
+struct UnknownKey {
+    key: String,
+    offset: usize,
+    in_override: bool,
+}
+
+fn check_unknown_keys(path: &Path, contents: &str) -> Result<()> {
+    let document = ImDocument::parse(contents).with_context(|| format!("invalid config file {}", path.display()))?;
+    let root = document.as_table();
+    let mut unknown = Vec::new();
+    collect_unknown_keys(root, false, &mut unknown);
+
+    if let Some(overrides) = root.get("overrides") {
+        if let Some(tables) = overrides.as_array_of_tables() {
+            for table in tables.iter() {
+                collect_unknown_keys(table, true, &mut unknown);
+            }
+        } else if let Some(array) = overrides.as_array() {
+            for table in array.iter().filter_map(|value| value.as_inline_table()) {
+                collect_unknown_keys(table, true, &mut unknown);
+            }
+        }
+    }
+
+    if unknown.is_empty() {
+        return Ok(());
+    }
+    let mut message = String::new();
+    for entry in unknown {
+        let (line, column) = line_column(contents, entry.offset);
+        let section = if entry.in_override { " in [[overrides]]" } else { "" };
+        message.push_str(&format!(
+            "unknown key `{}`{section} at {}:{line}:{column}",
+            entry.key,
+            path.display()
+        ));
+        if let Some(suggestion) = suggest_key(&entry.key) {
+            message.push_str(&format!(" (did you mean `{suggestion}`?)"));
+        }
+        message.push('\n');
+    }
+    message.push_str("hint: set `allow_unknown_keys = true` to ignore keys this version does not recognize");
+    bail!(message)
+}
+
+fn collect_unknown_keys(table: &dyn TableLike, in_override: bool, unknown: &mut Vec<UnknownKey>) {
+    for (key, _) in table.iter() {
+        // Known keys that an override cannot set are rejected when it is compiled.
+        if is_known_key(key) || (in_override && key == "files") {
+            continue;
+        }
+        let offset = table
+            .get_key_value(key)
+            .and_then(|(key, _)| key.span())
+            .map_or(0, |span| span.start);
+        unknown.push(UnknownKey {
+            key: key.to_string(),
+            offset,
+            in_override,
+        });
+    }
+}
+
+fn is_known_key(key: &str) -> bool {
+    KNOWN_KEYS.iter().any(|known| known == key)
+}
+
+fn suggest_key(key: &str) -> Option<&'static str> {
+    KNOWN_KEYS
+        .iter()
+        .map(|known| (strsim::jaro_winkler(key, known), known))
+        .filter(|(score, _)| *score > 0.8)
+        .max_by(|a, b| a.0.total_cmp(&b.0))
+        .map(|(_, known)| known.as_str())
+}
+
+fn line_column(text: &str, offset: usize) -> (usize, usize) {
+    let before = &text[..offset.min(text.len())];
+    let line = before.matches('\n').count() + 1;
+    let column = before.rsplit('\n').next().map_or(0, |tail| tail.chars().count()) + 1;
+    (line, column)
 }
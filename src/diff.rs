@@ -0,0 +1,49 @@
+use similar::TextDiff;
+
+const RED: &str = "\x1b[31m";
+const GREEN: &str = "\x1b[32m";
+const CYAN: &str = "\x1b[36m";
+const BOLD: &str = "\x1b[1m";
+const RESET: &str = "\x1b[0m";
+
+/// Renders a unified diff between `original` and `formatted`, labelling both sides with `path`.
+/// Returns an empty string when the inputs are identical.
+pub fn unified_diff(original: &str, formatted: &str, path: &str, context: usize) -> String {
+    if original == formatted {
+        return String::new();
+    }
+    TextDiff::from_lines(original, formatted)
+        .unified_diff()
+        .context_radius(context)
+        .header(path, path)
+        .to_string()
+}
+
+/// Adds ANSI colors to a diff produced by [`unified_diff`].
+pub fn colorize(diff: &str) -> String {
+    let mut colored = String::with_capacity(diff.len());
+    for line in diff.split_inclusive('\n') {
+        let body = line.trim_end_matches('\n');
+        let color = if body.starts_with("---") || body.starts_with("+++") {
+            Some(BOLD)
+        } else if body.starts_with("@@") {
+            Some(CYAN)
+        } else if body.starts_with('-') {
+            Some(RED)
+        } else if body.starts_with('+') {
+            Some(GREEN)
+        } else {
+            None
+        };
+        match color {
+            Some(color) => {
+                colored.push_str(color);
+                colored.push_str(body);
+                colored.push_str(RESET);
+                colored.push_str(&line[body.len()..]);
+            }
+            None => colored.push_str(line),
+        }
+    }
+    colored
+}
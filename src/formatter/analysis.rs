@@ -1,12 +1,17 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 use sv_parser::{Iter, NodeEvent, RefNode, RefNodes, SyntaxTree};
 
+/// The statement controlled by a header such as `if (...)`, `always` or `@(posedge clk)`.
 #[derive(Debug, Clone, Copy)]
 pub(crate) struct ByteSpan {
+    pub(crate) start: usize,
     pub(crate) end: usize,
+    /// The statement is a `begin`/`fork` block, which indents on its own.
+    pub(crate) block: bool,
 }
 
+/// Maps the first token of each statement header to the span of the statement it controls.
 pub(crate) fn collect_statement_spans(tree: &SyntaxTree) -> HashMap<usize, ByteSpan> {
     let mut spans = HashMap::new();
     for event in tree.into_iter().event() {
@@ -41,14 +46,204 @@ pub(crate) fn collect_statement_spans(tree: &SyntaxTree) -> HashMap<usize, ByteS
                     record_span(&node.nodes.0, RefNode::Statement(&node.nodes.2), &mut spans)
                 }
             },
+            NodeEvent::Enter(RefNode::AlwaysConstruct(construct)) => {
+                let keyword = match &construct.nodes.0 {
+                    sv_parser::AlwaysKeyword::Always(keyword)
+                    | sv_parser::AlwaysKeyword::AlwaysComb(keyword)
+                    | sv_parser::AlwaysKeyword::AlwaysLatch(keyword)
+                    | sv_parser::AlwaysKeyword::AlwaysFf(keyword) => keyword,
+                };
+                record_span(keyword, RefNode::Statement(&construct.nodes.1), &mut spans)
+            }
+            NodeEvent::Enter(RefNode::InitialConstruct(construct)) => record_span(
+                &construct.nodes.0,
+                RefNode::StatementOrNull(&construct.nodes.1),
+                &mut spans,
+            ),
+            NodeEvent::Enter(RefNode::FinalConstruct(construct)) => record_span(
+                &construct.nodes.0,
+                RefNode::Statement(&construct.nodes.1.nodes.0),
+                &mut spans,
+            ),
+            NodeEvent::Enter(RefNode::ProceduralTimingControlStatement(stmt)) => {
+                if let Some(header) = first_token_offset((&stmt.nodes.0).into())
+                    && let Some(span) = statement_span(RefNode::StatementOrNull(&stmt.nodes.1))
+                {
+                    spans.insert(header, span);
+                }
+            }
             _ => {}
         }
     }
     spans
 }
 
-pub(crate) fn collect_case_alignment(tree: &SyntaxTree) -> HashMap<usize, usize> {
-    let mut alignment = HashMap::new();
+/// Offsets of the first token of `node` and just past its last one.
+fn code_span(node: RefNode) -> Option<(usize, usize)> {
+    let mut whitespace_depth = 0usize;
+    let mut span = None;
+    for event in node.into_iter().event() {
+        match event {
+            NodeEvent::Enter(RefNode::WhiteSpace(_)) => whitespace_depth += 1,
+            NodeEvent::Leave(RefNode::WhiteSpace(_)) => whitespace_depth = whitespace_depth.saturating_sub(1),
+            NodeEvent::Enter(RefNode::Locate(loc)) if whitespace_depth == 0 => {
+                let end = loc.offset + loc.len;
+                span = Some(span.map_or((loc.offset, end), |(start, _)| (start, end)));
+            }
+            _ => {}
+        }
+    }
+    span
+}
+
+/// A construct whose body is indented: the offsets of its opening and closing keywords.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct BlockSpan {
+    pub(crate) open: usize,
+    pub(crate) close: usize,
+}
+
+/// Keywords that open and close indented blocks, by token offset.
+#[derive(Debug, Default)]
+pub(crate) struct BlockKeywords {
+    opens: HashSet<usize>,
+    closes: HashSet<usize>,
+}
+
+impl BlockKeywords {
+    pub(crate) fn new(spans: Vec<BlockSpan>) -> Self {
+        Self {
+            opens: spans.iter().map(|span| span.open).collect(),
+            closes: spans.iter().map(|span| span.close).collect(),
+        }
+    }
+
+    pub(crate) fn opens(&self, offset: usize) -> bool {
+        self.opens.contains(&offset)
+    }
+
+    pub(crate) fn closes(&self, offset: usize) -> bool {
+        self.closes.contains(&offset)
+    }
+}
+
+/// Collects every construct that indents its body, from design units down to `begin`/`end`.
+pub(crate) fn collect_blocks(tree: &SyntaxTree) -> Vec<BlockSpan> {
+    let mut blocks = Vec::new();
+    for event in tree.into_iter().event() {
+        let NodeEvent::Enter(node) = event else {
+            continue;
+        };
+        let open = match &node {
+            // The optional `initial` statement of a sequential UDP precedes `table`.
+            RefNode::SequentialBody(body) => Some(body.nodes.1.nodes.0.offset),
+            _ if is_block_node(&node) => None,
+            _ => continue,
+        };
+        if let Some((first, close)) = keyword_bounds(node.clone()) {
+            blocks.push(BlockSpan {
+                open: open.unwrap_or(first),
+                close,
+            });
+        }
+    }
+    blocks
+}
+
+fn is_block_node(node: &RefNode) -> bool {
+    matches!(
+        node,
+        RefNode::ModuleDeclarationNonansi(_)
+            | RefNode::ModuleDeclarationAnsi(_)
+            | RefNode::ModuleDeclarationWildcard(_)
+            | RefNode::InterfaceDeclarationNonansi(_)
+            | RefNode::InterfaceDeclarationAnsi(_)
+            | RefNode::InterfaceDeclarationWildcard(_)
+            | RefNode::ProgramDeclarationNonansi(_)
+            | RefNode::ProgramDeclarationAnsi(_)
+            | RefNode::ProgramDeclarationWildcard(_)
+            | RefNode::CheckerDeclaration(_)
+            | RefNode::ClassDeclaration(_)
+            | RefNode::InterfaceClassDeclaration(_)
+            | RefNode::PackageDeclaration(_)
+            | RefNode::ConfigDeclaration(_)
+            | RefNode::UdpDeclarationNonansi(_)
+            | RefNode::UdpDeclarationAnsi(_)
+            | RefNode::UdpDeclarationWildcard(_)
+            | RefNode::CombinationalBody(_)
+            | RefNode::FunctionDeclaration(_)
+            | RefNode::TaskDeclaration(_)
+            | RefNode::ClassConstructorDeclaration(_)
+            | RefNode::PropertyDeclaration(_)
+            | RefNode::SequenceDeclaration(_)
+            | RefNode::CovergroupDeclaration(_)
+            | RefNode::ClockingDeclarationLocal(_)
+            | RefNode::ClockingDeclarationGlobal(_)
+            | RefNode::SpecifyBlock(_)
+            | RefNode::GenerateRegion(_)
+            | RefNode::GenerateBlockMultiple(_)
+            | RefNode::CaseGenerateConstruct(_)
+            | RefNode::SeqBlock(_)
+            | RefNode::ParBlock(_)
+            | RefNode::CaseStatementNormal(_)
+            | RefNode::CaseStatementMatches(_)
+            | RefNode::CaseStatementInside(_)
+            | RefNode::RandcaseStatement(_)
+            | RefNode::RandsequenceStatement(_)
+            | RefNode::PropertyExprCase(_)
+    )
+}
+
+/// Offsets of the first and last keyword in `node`.
+fn keyword_bounds(node: RefNode) -> Option<(usize, usize)> {
+    let mut whitespace_depth = 0usize;
+    let mut bounds = None;
+    for event in node.into_iter().event() {
+        match event {
+            NodeEvent::Enter(RefNode::WhiteSpace(_)) => whitespace_depth += 1,
+            NodeEvent::Leave(RefNode::WhiteSpace(_)) => whitespace_depth = whitespace_depth.saturating_sub(1),
+            NodeEvent::Enter(RefNode::Keyword(keyword)) if whitespace_depth == 0 => {
+                let offset = keyword.nodes.0.offset;
+                bounds = Some(bounds.map_or((offset, offset), |(first, _)| (first, offset)));
+            }
+            _ => {}
+        }
+    }
+    bounds.filter(|(first, last)| first != last)
+}
+
+/// Offsets of the first token of every case item and randsequence production.
+pub(crate) fn collect_item_starts(tree: &SyntaxTree) -> Vec<usize> {
+    let mut starts = Vec::new();
+    for event in tree.into_iter().event() {
+        let NodeEvent::Enter(node) = event else {
+            continue;
+        };
+        if matches!(
+            node,
+            RefNode::CaseItem(_)
+                | RefNode::CaseInsideItem(_)
+                | RefNode::CasePatternItem(_)
+                | RefNode::CaseGenerateItem(_)
+                | RefNode::RandcaseItem(_)
+                | RefNode::Production(_)
+        ) && let Some((start, _)) = code_span(node)
+        {
+            starts.push(start);
+        }
+    }
+    starts
+}
+
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct CaseLabel {
+    pub(crate) start: usize,
+    pub(crate) colon: usize,
+}
+
+/// Collects the labels of every `case`/`randcase` statement, one group per statement.
+pub(crate) fn collect_case_labels(tree: &SyntaxTree) -> Vec<Vec<CaseLabel>> {
+    let mut groups = Vec::new();
     for event in tree.into_iter().event() {
         if let NodeEvent::Enter(RefNode::CaseStatement(stmt)) = event {
             if let sv_parser::CaseStatement::Normal(case) = stmt {
@@ -57,7 +252,7 @@ pub(crate) fn collect_case_alignment(tree: &SyntaxTree) -> HashMap<usize, usize>
                 for item in &case.nodes.4 {
                     collect_case_item(item, &mut entries);
                 }
-                apply_alignment(entries, &mut alignment);
+                groups.push(entries);
             }
         } else if let NodeEvent::Enter(RefNode::RandcaseStatement(stmt)) = event {
             let mut entries = Vec::new();
@@ -65,56 +260,625 @@ pub(crate) fn collect_case_alignment(tree: &SyntaxTree) -> HashMap<usize, usize>
             for item in &stmt.nodes.2 {
                 collect_randcase_item(item, &mut entries);
             }
-            apply_alignment(entries, &mut alignment);
+            groups.push(entries);
         }
     }
+    groups
+}
+
+/// Maps each label colon to the padding that lines it up with the widest label in its group.
+pub(crate) fn case_alignment(groups: Vec<Vec<CaseLabel>>) -> HashMap<usize, usize> {
+    let mut alignment = HashMap::new();
+    for entries in groups {
+        let entries: Vec<(usize, usize)> = entries
+            .into_iter()
+            .map(|label| (label.colon, label.colon.saturating_sub(label.start)))
+            .collect();
+        apply_alignment(entries, &mut alignment);
+    }
     alignment
 }
 
-fn record_span<'a>(keyword: &'a sv_parser::Keyword, node: RefNode<'a>, spans: &mut HashMap<usize, ByteSpan>) {
-    if let Some(span) = node_span(node) {
-        spans.insert(keyword.nodes.0.offset, span);
+/// A bracketed, comma-separated list laid out one entry per line with aligned columns.
+#[derive(Debug, Clone)]
+pub(crate) struct AlignedList {
+    pub(crate) kind: ListKind,
+    pub(crate) open: usize,
+    pub(crate) close: usize,
+    pub(crate) entries: Vec<ListEntry>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ListKind {
+    Ports,
+    Parameters,
+    Connections,
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct ListEntry {
+    pub(crate) start: usize,
+    pub(crate) end: usize,
+    /// Offset of the token starting each column, if any.
+    pub(crate) columns: Vec<Option<usize>>,
+}
+
+const PORT_COLUMNS: usize = 5;
+const PORT_DIRECTION: usize = 0;
+const PORT_TYPE: usize = 1;
+const PORT_PACKED: usize = 2;
+const PORT_NAME: usize = 3;
+const PORT_REST: usize = 4;
+
+/// Collects every ANSI port list with the columns of its ports.
+pub(crate) fn collect_port_lists(tree: &SyntaxTree) -> Vec<AlignedList> {
+    let mut lists = Vec::new();
+    for event in tree.into_iter().event() {
+        let NodeEvent::Enter(RefNode::ListOfPortDeclarations(list)) = event else {
+            continue;
+        };
+        let (open, ports, close) = &list.nodes.0.nodes;
+        let entries = ports
+            .iter()
+            .flat_map(|ports| ports.contents())
+            .map(|(attributes, port)| port_entry(attributes, port))
+            .collect::<Option<Vec<_>>>();
+        if let Some(entries) = entries.filter(|entries| !entries.is_empty()) {
+            lists.push(AlignedList {
+                kind: ListKind::Ports,
+                open: open.nodes.0.offset,
+                close: close.nodes.0.offset,
+                entries,
+            });
+        }
     }
+    lists
 }
 
-fn node_span(node: RefNode) -> Option<ByteSpan> {
-    let mut start = None;
-    let mut end = 0;
-    for event in node.into_iter().event() {
-        if let NodeEvent::Enter(RefNode::Locate(loc)) = event {
-            if start.is_none() {
-                start = Some(loc.offset);
+const PARAM_COLUMNS: usize = 4;
+const PARAM_KEYWORD: usize = 0;
+const PARAM_TYPE: usize = 1;
+const PARAM_NAME: usize = 2;
+const PARAM_VALUE: usize = 3;
+
+/// Collects every parameter port list, one entry per parameter.
+pub(crate) fn collect_parameter_lists(tree: &SyntaxTree) -> Vec<AlignedList> {
+    let mut lists = Vec::new();
+    for event in tree.into_iter().event() {
+        let NodeEvent::Enter(RefNode::ParameterPortList(list)) = event else {
+            continue;
+        };
+        let mut entries = Vec::new();
+        let (open, close) = match list {
+            sv_parser::ParameterPortList::Assignment(list) => {
+                let (open, (assignments, declarations), close) = &list.nodes.1.nodes;
+                for assignment in assignments.nodes.0.contents() {
+                    entries.extend(param_assignment_entry(assignment, None, None));
+                }
+                for (_, declaration) in declarations {
+                    entries.extend(parameter_port_entries(declaration));
+                }
+                (open, close)
+            }
+            sv_parser::ParameterPortList::Declaration(list) => {
+                let (open, declarations, close) = &list.nodes.1.nodes;
+                for declaration in declarations.contents() {
+                    entries.extend(parameter_port_entries(declaration));
+                }
+                (open, close)
+            }
+            sv_parser::ParameterPortList::Empty(_) => continue,
+        };
+        lists.push(AlignedList {
+            kind: ListKind::Parameters,
+            open: open.nodes.0.offset,
+            close: close.nodes.0.offset,
+            entries,
+        });
+    }
+    lists
+}
+
+/// Collects the single-parameter declarations outside parameter port lists, one row each.
+pub(crate) fn collect_parameter_declarations(tree: &SyntaxTree) -> Vec<ListEntry> {
+    let mut rows = Vec::new();
+    let mut port_list_depth = 0usize;
+    for event in tree.into_iter().event() {
+        match event {
+            NodeEvent::Enter(RefNode::ParameterPortList(_)) => port_list_depth += 1,
+            NodeEvent::Leave(RefNode::ParameterPortList(_)) => port_list_depth -= 1,
+            NodeEvent::Enter(node @ (RefNode::ParameterDeclaration(_) | RefNode::LocalParameterDeclaration(_)))
+                if port_list_depth == 0 =>
+            {
+                if let [row] = parameter_declaration_entries(node).as_slice() {
+                    rows.push(row.clone());
+                }
+            }
+            _ => {}
+        }
+    }
+    rows
+}
+
+fn parameter_port_entries(declaration: &sv_parser::ParameterPortDeclaration) -> Vec<ListEntry> {
+    use sv_parser::ParameterPortDeclaration;
+
+    match declaration {
+        ParameterPortDeclaration::ParameterDeclaration(node) => {
+            parameter_declaration_entries(RefNode::ParameterDeclaration(node))
+        }
+        ParameterPortDeclaration::LocalParameterDeclaration(node) => {
+            parameter_declaration_entries(RefNode::LocalParameterDeclaration(node))
+        }
+        ParameterPortDeclaration::ParamList(node) => {
+            let (data_type, assignments) = &node.nodes;
+            param_assignment_entries(assignments, None, first_token_offset(data_type.into()))
+        }
+        ParameterPortDeclaration::TypeList(node) => {
+            let (type_keyword, assignments) = &node.nodes;
+            type_assignment_entries(assignments, None, Some(type_keyword.nodes.0.offset))
+        }
+    }
+}
+
+fn parameter_declaration_entries(node: RefNode) -> Vec<ListEntry> {
+    use sv_parser::{LocalParameterDeclaration, ParameterDeclaration};
+
+    match node {
+        RefNode::ParameterDeclaration(ParameterDeclaration::Param(node)) => {
+            let (keyword, data_type, assignments) = &node.nodes;
+            param_assignment_entries(assignments, Some(keyword), first_token_offset(data_type.into()))
+        }
+        RefNode::ParameterDeclaration(ParameterDeclaration::Type(node)) => {
+            let (keyword, type_keyword, assignments) = &node.nodes;
+            type_assignment_entries(assignments, Some(keyword), Some(type_keyword.nodes.0.offset))
+        }
+        RefNode::LocalParameterDeclaration(LocalParameterDeclaration::Param(node)) => {
+            let (keyword, data_type, assignments) = &node.nodes;
+            param_assignment_entries(assignments, Some(keyword), first_token_offset(data_type.into()))
+        }
+        RefNode::LocalParameterDeclaration(LocalParameterDeclaration::Type(node)) => {
+            let (keyword, type_keyword, assignments) = &node.nodes;
+            type_assignment_entries(assignments, Some(keyword), Some(type_keyword.nodes.0.offset))
+        }
+        _ => Vec::new(),
+    }
+}
+
+/// One entry per assignment; the keyword and type only belong to the first.
+fn param_assignment_entries(
+    assignments: &sv_parser::ListOfParamAssignments,
+    keyword: Option<&sv_parser::Keyword>,
+    data_type: Option<usize>,
+) -> Vec<ListEntry> {
+    let mut entries = Vec::new();
+    let mut header = (keyword, data_type);
+    for assignment in assignments.nodes.0.contents() {
+        let (keyword, data_type) = std::mem::take(&mut header);
+        entries.extend(param_assignment_entry(assignment, keyword, data_type));
+    }
+    entries
+}
+
+fn param_assignment_entry(
+    assignment: &sv_parser::ParamAssignment,
+    keyword: Option<&sv_parser::Keyword>,
+    data_type: Option<usize>,
+) -> Option<ListEntry> {
+    let (name, _, value) = &assignment.nodes;
+    let value = value.as_ref().map(|(eq, _)| eq.nodes.0.offset);
+    parameter_entry(
+        RefNode::ParamAssignment(assignment),
+        keyword,
+        data_type,
+        name.into(),
+        value,
+    )
+}
+
+fn type_assignment_entries(
+    assignments: &sv_parser::ListOfTypeAssignments,
+    keyword: Option<&sv_parser::Keyword>,
+    type_keyword: Option<usize>,
+) -> Vec<ListEntry> {
+    let mut entries = Vec::new();
+    let mut header = (keyword, type_keyword);
+    for assignment in assignments.nodes.0.contents() {
+        let (keyword, type_keyword) = std::mem::take(&mut header);
+        let (name, value) = &assignment.nodes;
+        let value = value.as_ref().map(|(eq, _)| eq.nodes.0.offset);
+        entries.extend(parameter_entry(
+            RefNode::TypeAssignment(assignment),
+            keyword,
+            type_keyword,
+            name.into(),
+            value,
+        ));
+    }
+    entries
+}
+
+fn parameter_entry(
+    assignment: RefNode,
+    keyword: Option<&sv_parser::Keyword>,
+    data_type: Option<usize>,
+    name: RefNodes,
+    value: Option<usize>,
+) -> Option<ListEntry> {
+    let (name_start, end) = code_span(assignment)?;
+    let keyword = keyword.map(|keyword| keyword.nodes.0.offset);
+    let mut columns = vec![None; PARAM_COLUMNS];
+    columns[PARAM_KEYWORD] = keyword;
+    columns[PARAM_TYPE] = data_type;
+    columns[PARAM_NAME] = first_token_offset(name).or(Some(name_start));
+    columns[PARAM_VALUE] = value;
+    let start = keyword.or(data_type).unwrap_or(name_start);
+    Some(ListEntry { start, end, columns })
+}
+
+const CONNECTION_COLUMNS: usize = 2;
+const CONNECTION_NAME: usize = 0;
+const CONNECTION_VALUE: usize = 1;
+
+/// Collects the named port connections and parameter overrides of every instance.
+pub(crate) fn collect_connection_lists(tree: &SyntaxTree) -> Vec<AlignedList> {
+    let mut lists = Vec::new();
+    for event in tree.into_iter().event() {
+        let (parameters, instances) = match event {
+            NodeEvent::Enter(RefNode::ModuleInstantiation(node)) => (&node.nodes.1, &node.nodes.2),
+            NodeEvent::Enter(RefNode::InterfaceInstantiation(node)) => (&node.nodes.1, &node.nodes.2),
+            NodeEvent::Enter(RefNode::ProgramInstantiation(node)) => (&node.nodes.1, &node.nodes.2),
+            _ => continue,
+        };
+        if let Some(parameters) = parameters {
+            let (open, assignments, close) = &parameters.nodes.1.nodes;
+            if let Some(sv_parser::ListOfParameterAssignments::Named(assignments)) = assignments {
+                let entries = assignments
+                    .nodes
+                    .0
+                    .contents()
+                    .into_iter()
+                    .map(|assignment| {
+                        let (dot, _, value) = &assignment.nodes;
+                        connection_entry(
+                            RefNode::NamedParameterAssignment(assignment),
+                            dot,
+                            Some(value.nodes.0.nodes.0.offset),
+                        )
+                    })
+                    .collect();
+                push_connection_list(open, close, entries, &mut lists);
+            }
+        }
+        for instance in instances.contents() {
+            let (open, connections, close) = &instance.nodes.1.nodes;
+            if let Some(sv_parser::ListOfPortConnections::Named(connections)) = connections {
+                let entries = connections
+                    .nodes
+                    .0
+                    .contents()
+                    .into_iter()
+                    .map(|connection| match connection {
+                        sv_parser::NamedPortConnection::Identifier(node) => {
+                            let (_, dot, _, value) = &node.nodes;
+                            let value = value.as_ref().map(|paren| paren.nodes.0.nodes.0.offset);
+                            connection_entry(RefNode::NamedPortConnection(connection), dot, value)
+                        }
+                        sv_parser::NamedPortConnection::Asterisk(node) => {
+                            connection_entry(RefNode::NamedPortConnection(connection), &node.nodes.1, None)
+                        }
+                    })
+                    .collect();
+                push_connection_list(open, close, entries, &mut lists);
+            }
+        }
+    }
+    lists
+}
+
+fn connection_entry(connection: RefNode, dot: &sv_parser::Symbol, value: Option<usize>) -> Option<ListEntry> {
+    let (start, end) = code_span(connection)?;
+    let mut columns = vec![None; CONNECTION_COLUMNS];
+    columns[CONNECTION_NAME] = Some(dot.nodes.0.offset);
+    columns[CONNECTION_VALUE] = value;
+    Some(ListEntry { start, end, columns })
+}
+
+fn push_connection_list(
+    open: &sv_parser::Symbol,
+    close: &sv_parser::Symbol,
+    entries: Vec<Option<ListEntry>>,
+    lists: &mut Vec<AlignedList>,
+) {
+    if let Some(entries) = entries.into_iter().collect::<Option<Vec<_>>>() {
+        lists.push(AlignedList {
+            kind: ListKind::Connections,
+            open: open.nodes.0.offset,
+            close: close.nodes.0.offset,
+            entries,
+        });
+    }
+}
+
+const ASSIGNMENT_COLUMNS: usize = 2;
+const ASSIGNMENT_TARGET: usize = 0;
+const ASSIGNMENT_OPERATOR: usize = 1;
+
+/// Collects single-target continuous, blocking and nonblocking assignments, one row each.
+pub(crate) fn collect_assignments(tree: &SyntaxTree) -> Vec<ListEntry> {
+    use sv_parser::{BlockItemDeclaration, BlockingAssignment, ContinuousAssign, DataDeclaration};
+
+    let mut rows = Vec::new();
+    for node in tree {
+        let (operator, assignment) = match node {
+            RefNode::BlockItemDeclaration(BlockItemDeclaration::Data(data)) => {
+                let DataDeclaration::Variable(declaration) = &data.nodes.1 else {
+                    continue;
+                };
+                match implicit_assignment(declaration) {
+                    Some((variable, (operator, _))) => (operator, RefNode::VariableDeclAssignmentVariable(variable)),
+                    None => continue,
+                }
+            }
+            RefNode::ContinuousAssign(ContinuousAssign::Net(assign)) => match assign.nodes.3.nodes.0.contents()[..] {
+                [assignment] => (&assignment.nodes.1, RefNode::NetAssignment(assignment)),
+                _ => continue,
+            },
+            RefNode::ContinuousAssign(ContinuousAssign::Variable(assign)) => {
+                match assign.nodes.2.nodes.0.contents()[..] {
+                    [assignment] => (&assignment.nodes.1, RefNode::VariableAssignment(assignment)),
+                    _ => continue,
+                }
+            }
+            RefNode::BlockingAssignment(BlockingAssignment::Variable(assignment)) => {
+                (&assignment.nodes.1, node.clone())
             }
-            end = loc.offset + loc.len;
+            RefNode::BlockingAssignment(BlockingAssignment::NonrangeVariable(assignment)) => {
+                (&assignment.nodes.1, node.clone())
+            }
+            RefNode::BlockingAssignment(BlockingAssignment::HierarchicalVariable(assignment)) => {
+                (&assignment.nodes.3, node.clone())
+            }
+            RefNode::BlockingAssignment(BlockingAssignment::OperatorAssignment(assignment)) => {
+                (&assignment.nodes.1.nodes.0, node.clone())
+            }
+            RefNode::NonblockingAssignment(assignment) => (&assignment.nodes.1, node.clone()),
+            _ => continue,
+        };
+        if let Some((start, _)) = code_span(node)
+            && let Some((_, end)) = code_span(assignment)
+        {
+            let mut columns = vec![None; ASSIGNMENT_COLUMNS];
+            columns[ASSIGNMENT_TARGET] = Some(start);
+            columns[ASSIGNMENT_OPERATOR] = Some(operator.nodes.0.offset);
+            rows.push(ListEntry { start, end, columns });
+        }
+    }
+    rows
+}
+
+/// The variable and initializer of an assignment such as `x = 0;` parsed as a declaration.
+fn implicit_assignment(
+    declaration: &sv_parser::DataDeclarationVariable,
+) -> Option<(
+    &sv_parser::VariableDeclAssignmentVariable,
+    &(sv_parser::Symbol, sv_parser::Expression),
+)> {
+    use sv_parser::VariableDeclAssignment;
+
+    if !is_untyped(declaration) {
+        return None;
+    }
+    match declaration.nodes.4.nodes.0.contents()[..] {
+        [VariableDeclAssignment::Variable(variable)] if variable.nodes.1.is_empty() => {
+            Some((&**variable, variable.nodes.2.as_ref()?))
+        }
+        _ => None,
+    }
+}
+
+/// The declaration has no type, keyword or dimension, so it is really an assignment.
+fn is_untyped(declaration: &sv_parser::DataDeclarationVariable) -> bool {
+    use sv_parser::DataTypeOrImplicit;
+
+    let (constant, var, lifetime, data_type, _, _) = &declaration.nodes;
+    let DataTypeOrImplicit::ImplicitDataType(implicit) = data_type else {
+        return false;
+    };
+    let (signing, dimensions) = &implicit.nodes;
+    constant.is_none() && var.is_none() && lifetime.is_none() && signing.is_none() && dimensions.is_empty()
+}
+
+const DECLARATION_COLUMNS: usize = 5;
+const DECLARATION_TYPE: usize = 0;
+const DECLARATION_PACKED: usize = 1;
+const DECLARATION_NAME: usize = 2;
+const DECLARATION_UNPACKED: usize = 3;
+const DECLARATION_VALUE: usize = 4;
+
+/// Collects the single-name variable and net declarations, one row each.
+pub(crate) fn collect_declarations(tree: &SyntaxTree) -> Vec<ListEntry> {
+    use sv_parser::{DataDeclaration, NetDeclaration, VariableDeclAssignment};
+
+    let mut rows = Vec::new();
+    for node in tree {
+        let mut columns = vec![None; DECLARATION_COLUMNS];
+        let assignment = match node {
+            RefNode::DataDeclaration(DataDeclaration::Variable(declaration)) if !is_untyped(declaration) => {
+                let (_, _, _, data_type, assignments, _) = &declaration.nodes;
+                columns[DECLARATION_PACKED] = packed_dimension(data_type);
+                let [assignment] = assignments.nodes.0.contents()[..] else {
+                    continue;
+                };
+                match assignment {
+                    VariableDeclAssignment::Variable(variable) => {
+                        let (name, dimensions, value) = &variable.nodes;
+                        columns[DECLARATION_NAME] = first_token_offset(name.into());
+                        columns[DECLARATION_UNPACKED] =
+                            dimensions.first().and_then(|dim| first_token_offset(dim.into()));
+                        columns[DECLARATION_VALUE] = value.as_ref().map(|(eq, _)| eq.nodes.0.offset);
+                    }
+                    VariableDeclAssignment::DynamicArray(array) => {
+                        let (name, dimension, _, value) = &array.nodes;
+                        columns[DECLARATION_NAME] = first_token_offset(name.into());
+                        columns[DECLARATION_UNPACKED] = first_token_offset(dimension.into());
+                        columns[DECLARATION_VALUE] = value.as_ref().map(|(eq, _)| eq.nodes.0.offset);
+                    }
+                    VariableDeclAssignment::Class(class) => {
+                        let (name, (eq, _)) = &class.nodes;
+                        columns[DECLARATION_NAME] = first_token_offset(name.into());
+                        columns[DECLARATION_VALUE] = Some(eq.nodes.0.offset);
+                    }
+                }
+                RefNode::VariableDeclAssignment(assignment)
+            }
+            RefNode::NetDeclaration(NetDeclaration::NetType(declaration)) => {
+                let (_, _, _, data_type, _, assignments, _) = &declaration.nodes;
+                columns[DECLARATION_PACKED] = packed_dimension(data_type);
+                let [assignment] = assignments.nodes.0.contents()[..] else {
+                    continue;
+                };
+                let (name, dimensions, value) = &assignment.nodes;
+                columns[DECLARATION_NAME] = first_token_offset(name.into());
+                columns[DECLARATION_UNPACKED] = dimensions.first().and_then(|dim| first_token_offset(dim.into()));
+                columns[DECLARATION_VALUE] = value.as_ref().map(|(eq, _)| eq.nodes.0.offset);
+                RefNode::NetDeclAssignment(assignment)
+            }
+            _ => continue,
+        };
+        if let Some((start, _)) = code_span(node)
+            && let Some((_, end)) = code_span(assignment)
+        {
+            columns[DECLARATION_TYPE] = Some(start).filter(|&start| columns[DECLARATION_PACKED] != Some(start));
+            rows.push(ListEntry { start, end, columns });
+        }
+    }
+    rows
+}
+
+/// Offset of the first packed dimension written as part of a declaration's data type.
+fn packed_dimension(data_type: &sv_parser::DataTypeOrImplicit) -> Option<usize> {
+    use sv_parser::{DataType, DataTypeOrImplicit};
+
+    let dimensions = match data_type {
+        DataTypeOrImplicit::ImplicitDataType(implicit) => &implicit.nodes.1,
+        DataTypeOrImplicit::DataType(data_type) => match &**data_type {
+            DataType::Vector(vector) => &vector.nodes.2,
+            DataType::StructUnion(struct_union) => &struct_union.nodes.3,
+            DataType::Enum(enumeration) => &enumeration.nodes.3,
+            DataType::Type(type_name) => &type_name.nodes.2,
+            _ => return None,
+        },
+    };
+    dimensions.first().and_then(|dim| first_token_offset(dim.into()))
+}
+
+fn port_entry(attributes: &[sv_parser::AttributeInstance], port: &sv_parser::AnsiPortDeclaration) -> Option<ListEntry> {
+    use sv_parser::{AnsiPortDeclaration, NetPortHeaderOrInterfacePortHeader};
+
+    let mut columns = vec![None; PORT_COLUMNS];
+    match port {
+        AnsiPortDeclaration::Net(port) => {
+            let (header, name, unpacked, default) = &port.nodes;
+            match header {
+                Some(NetPortHeaderOrInterfacePortHeader::NetPortHeader(header)) => {
+                    columns[PORT_DIRECTION] = header.nodes.0.as_ref().and_then(|dir| first_token_offset(dir.into()));
+                    type_columns(RefNode::NetPortType(&header.nodes.1), &mut columns);
+                }
+                Some(NetPortHeaderOrInterfacePortHeader::InterfacePortHeader(header)) => {
+                    columns[PORT_TYPE] = code_span(RefNode::InterfacePortHeader(header)).map(|(start, _)| start);
+                }
+                None => {}
+            }
+            columns[PORT_NAME] = first_token_offset(name.into());
+            columns[PORT_REST] = unpacked
+                .first()
+                .and_then(|dim| first_token_offset(dim.into()))
+                .or_else(|| default.as_ref().map(|(eq, _)| eq.nodes.0.offset));
+        }
+        AnsiPortDeclaration::Variable(port) => {
+            let (header, name, unpacked, default) = &port.nodes;
+            if let Some(header) = header {
+                columns[PORT_DIRECTION] = header.nodes.0.as_ref().and_then(|dir| first_token_offset(dir.into()));
+                type_columns(RefNode::VariablePortType(&header.nodes.1), &mut columns);
+            }
+            columns[PORT_NAME] = first_token_offset(name.into());
+            columns[PORT_REST] = unpacked
+                .first()
+                .and_then(|dim| first_token_offset(dim.into()))
+                .or_else(|| default.as_ref().map(|(eq, _)| eq.nodes.0.offset));
+        }
+        AnsiPortDeclaration::Paren(port) => {
+            let (direction, dot, _, _) = &port.nodes;
+            columns[PORT_DIRECTION] = direction.as_ref().and_then(|dir| first_token_offset(dir.into()));
+            columns[PORT_NAME] = Some(dot.nodes.0.offset);
         }
     }
-    start.map(|_| ByteSpan { end })
+    let (start, end) = code_span(RefNode::AnsiPortDeclaration(port))?;
+    let start = attributes
+        .first()
+        .and_then(|attribute| first_token_offset(attribute.into()))
+        .unwrap_or(start);
+    Some(ListEntry { start, end, columns })
+}
+
+/// Splits a port's data type into the type itself and its packed dimensions.
+fn type_columns(node: RefNode, columns: &mut [Option<usize>]) {
+    let packed = node.clone().into_iter().find_map(|node| match node {
+        RefNode::PackedDimension(dim) => first_token_offset(dim.into()),
+        _ => None,
+    });
+    let start = code_span(node).map(|(start, _)| start);
+    columns[PORT_TYPE] = start.filter(|&start| Some(start) != packed);
+    columns[PORT_PACKED] = packed;
+}
+
+fn record_span<'a>(keyword: &'a sv_parser::Keyword, node: RefNode<'a>, spans: &mut HashMap<usize, ByteSpan>) {
+    if let Some(span) = statement_span(node) {
+        spans.insert(keyword.nodes.0.offset, span);
+    }
+}
+
+fn statement_span(node: RefNode) -> Option<ByteSpan> {
+    let statement = match &node {
+        RefNode::StatementOrNull(sv_parser::StatementOrNull::Statement(statement)) => Some(&**statement),
+        RefNode::Statement(statement) => Some(*statement),
+        _ => None,
+    };
+    let block = statement.is_some_and(|statement| {
+        matches!(
+            statement.nodes.2,
+            sv_parser::StatementItem::SeqBlock(_) | sv_parser::StatementItem::ParBlock(_)
+        )
+    });
+    let (start, end) = code_span(node)?;
+    Some(ByteSpan { start, end, block })
 }
 
-fn collect_case_item(item: &sv_parser::CaseItem, entries: &mut Vec<(usize, usize)>) {
+fn collect_case_item(item: &sv_parser::CaseItem, entries: &mut Vec<CaseLabel>) {
     match item {
         sv_parser::CaseItem::NonDefault(node) => {
             if let Some(start) = first_token_offset((&node.nodes.0).into()) {
-                let symbol = &node.nodes.1.nodes.0;
-                let width = symbol.offset.saturating_sub(start);
-                entries.push((symbol.offset, width));
+                let colon = node.nodes.1.nodes.0.offset;
+                entries.push(CaseLabel { start, colon });
             }
         }
         sv_parser::CaseItem::Default(node) => {
             if let Some(symbol) = &node.nodes.1 {
                 let start = node.nodes.0.nodes.0.offset;
-                let width = symbol.nodes.0.offset.saturating_sub(start);
-                entries.push((symbol.nodes.0.offset, width));
+                entries.push(CaseLabel {
+                    start,
+                    colon: symbol.nodes.0.offset,
+                });
             }
         }
     }
 }
 
-fn collect_randcase_item(item: &sv_parser::RandcaseItem, entries: &mut Vec<(usize, usize)>) {
+fn collect_randcase_item(item: &sv_parser::RandcaseItem, entries: &mut Vec<CaseLabel>) {
     if let Some(start) = first_token_offset((&item.nodes.0).into()) {
-        let symbol = &item.nodes.1.nodes.0;
-        let width = symbol.offset.saturating_sub(start);
-        entries.push((symbol.offset, width));
+        let colon = item.nodes.1.nodes.0.offset;
+        entries.push(CaseLabel { start, colon });
     }
 }
 
@@ -0,0 +1,122 @@
+use std::borrow::Cow;
+
+use super::lexer::{Token, TokenKind, is_keyword};
+use crate::config::{FormatConfig, KeywordCase, LetterCase};
+
+/// Text written for `token` once `keyword_case` and the number case options are applied.
+pub(crate) fn token_text<'t>(token: &'t Token, config: &FormatConfig) -> Cow<'t, str> {
+    match token.kind {
+        TokenKind::Keyword if config.keyword_case == KeywordCase::Lower => Cow::Owned(token.text.to_ascii_lowercase()),
+        TokenKind::Number => normalize_number(&token.text, config),
+        _ => Cow::Borrowed(&token.text),
+    }
+}
+
+/// Applies `number_base_case` to the base of a based literal such as `8'hFF` and
+/// `number_digit_case` to its digits, which include `x` and `z`. Works on whole literals and
+/// on their size, base and value parts alike.
+fn normalize_number<'t>(text: &'t str, config: &FormatConfig) -> Cow<'t, str> {
+    if config.number_base_case == LetterCase::Preserve && config.number_digit_case == LetterCase::Preserve {
+        return Cow::Borrowed(text);
+    }
+    let (size, value) = match text.find('\'') {
+        Some(quote) => text.split_at(quote + 1),
+        None => ("", text),
+    };
+    let base_len = if size.is_empty() {
+        0
+    } else {
+        let signed = usize::from(value.starts_with(['s', 'S']));
+        let base = value[signed..].starts_with(['b', 'B', 'o', 'O', 'd', 'D', 'h', 'H']);
+        // `'0`, `'x` and the like have no base.
+        if base { signed + 1 } else { 0 }
+    };
+    let (base, digits) = value.split_at(base_len);
+    Cow::Owned(format!(
+        "{size}{}{}",
+        apply_case(base, config.number_base_case),
+        apply_case(digits, config.number_digit_case)
+    ))
+}
+
+fn apply_case(text: &str, case: LetterCase) -> String {
+    match case {
+        LetterCase::Preserve => text.to_string(),
+        LetterCase::Lower => text.to_ascii_lowercase(),
+        LetterCase::Upper => text.to_ascii_uppercase(),
+    }
+}
+
+/// Lowercases the words of `source` that spell a keyword in another case, for code that only
+/// parses once its keywords are lowercase. Comments, strings, macros, system tasks, escaped
+/// identifiers and `` `define `` bodies are left alone. Returns `None` when nothing changes.
+pub(crate) fn lowercase_keywords(source: &str) -> Option<String> {
+    let mut result = String::with_capacity(source.len());
+    let bytes = source.as_bytes();
+    let mut pos = 0;
+    let mut changed = false;
+    while pos < bytes.len() {
+        let start = pos;
+        let byte = bytes[pos];
+        match byte {
+            b'/' if bytes.get(pos + 1) == Some(&b'/') => pos = line_end(source, pos),
+            b'/' if bytes.get(pos + 1) == Some(&b'*') => {
+                pos = source[pos + 2..]
+                    .find("*/")
+                    .map_or(bytes.len(), |index| pos + 2 + index + 2);
+            }
+            b'"' => {
+                pos += 1;
+                while pos < bytes.len() && bytes[pos] != b'"' {
+                    pos += if bytes[pos] == b'\\' { 2 } else { 1 };
+                }
+                pos = (pos + 1).min(bytes.len());
+            }
+            b'\\' => {
+                pos = source[pos..]
+                    .find(char::is_whitespace)
+                    .map_or(bytes.len(), |index| pos + index)
+            }
+            b'`' if source[pos..].starts_with("`define") => {
+                // The body runs to the first line that does not end in a continuation.
+                pos = line_end(source, pos);
+                while source[..pos].trim_end_matches(['\r', ' ', '\t']).ends_with('\\') && pos < bytes.len() {
+                    pos = line_end(source, pos + 1);
+                }
+            }
+            b'`' | b'$' => pos = word_end(bytes, pos + 1),
+            _ if byte.is_ascii_alphabetic() || byte == b'_' => {
+                pos = word_end(bytes, pos);
+                let word = &source[start..pos];
+                if is_keyword(word) && word.bytes().any(|byte| byte.is_ascii_uppercase()) {
+                    result.push_str(&word.to_ascii_lowercase());
+                    changed = true;
+                    continue;
+                }
+            }
+            _ if byte.is_ascii_digit() || byte == b'\'' => {
+                // Keeps the base of literals such as `8'HFF` from being read as a word.
+                pos += 1;
+                while pos < bytes.len()
+                    && (bytes[pos].is_ascii_alphanumeric() || matches!(bytes[pos], b'_' | b'\'' | b'?'))
+                {
+                    pos += 1;
+                }
+            }
+            _ => pos += source[pos..].chars().next().map_or(1, char::len_utf8),
+        }
+        result.push_str(&source[start..pos]);
+    }
+    changed.then_some(result)
+}
+
+fn line_end(text: &str, pos: usize) -> usize {
+    text[pos..].find('\n').map_or(text.len(), |index| pos + index)
+}
+
+fn word_end(bytes: &[u8], pos: usize) -> usize {
+    bytes[pos..]
+        .iter()
+        .position(|&byte| !(byte.is_ascii_alphanumeric() || byte == b'_' || byte == b'$'))
+        .map_or(bytes.len(), |index| pos + index)
+}
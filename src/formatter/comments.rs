@@ -0,0 +1,49 @@
+use crate::config::FormatConfig;
+
+/// Moves the `//` comments ending consecutive lines of `text` to a common column, one past the
+/// longest code among them. `comments` holds the byte offsets, in order, of the comments that
+/// follow code on their line. A comment that would run past `max_line_length` at that column
+/// keeps a single space instead.
+pub(crate) fn align_trailing_comments(text: String, comments: &[usize], config: &FormatConfig) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut pos = 0;
+    let mut group_start = 0;
+    for index in 1..=comments.len() {
+        let continues = index < comments.len() && text[comments[index - 1]..comments[index]].matches('\n').count() == 1;
+        if continues {
+            continue;
+        }
+        let group = &comments[group_start..index];
+        group_start = index;
+        if group.len() < 2 {
+            continue;
+        }
+        let lines: Vec<(usize, usize, usize)> = group.iter().map(|&offset| comment_line(&text, offset)).collect();
+        let mut column = lines.iter().map(|&(_, code_width, _)| code_width).max().unwrap_or(0) + 1;
+        if config.snap_trailing_comments && config.indent_width > 0 {
+            column = column.next_multiple_of(config.indent_width);
+        }
+        for (&offset, &(code_end, code_width, comment_width)) in group.iter().zip(&lines) {
+            let fits = config.max_line_length == 0 || column + comment_width <= config.max_line_length;
+            let padding = if fits { column - code_width } else { 1 };
+            result.push_str(&text[pos..code_end]);
+            result.push_str(&" ".repeat(padding));
+            pos = offset;
+        }
+    }
+    result.push_str(&text[pos..]);
+    result
+}
+
+/// Byte offset where the code before the comment at `offset` ends, and the widths of that
+/// code and of the comment.
+fn comment_line(text: &str, offset: usize) -> (usize, usize, usize) {
+    let line_start = text[..offset].rfind('\n').map_or(0, |index| index + 1);
+    let code = text[line_start..offset].trim_end();
+    let line_end = text[offset..].find('\n').map_or(text.len(), |index| offset + index);
+    (
+        line_start + code.len(),
+        code.chars().count(),
+        text[offset..line_end].chars().count(),
+    )
+}
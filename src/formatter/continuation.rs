@@ -0,0 +1,187 @@
+use super::{
+    analysis::BlockKeywords,
+    lexer::{Token, TokenKind},
+};
+use crate::config::FormatConfig;
+
+/// How to indent a line.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum LineIndent {
+    /// Indent to this block level.
+    Level(usize),
+    /// Align to a character column; `level` is the block level of the line the column was
+    /// taken from, written as tabs when `use_tabs` is set.
+    Column { level: usize, column: usize },
+}
+
+impl LineIndent {
+    /// Block level the line's own brackets and statements are indented relative to.
+    fn level(self) -> usize {
+        match self {
+            Self::Level(level) | Self::Column { level, .. } => level,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct OpenBracket {
+    /// Level of the line holding the bracket.
+    base_level: usize,
+    column: usize,
+    /// Column of the first token after the bracket, when it is on the same line.
+    content_column: Option<usize>,
+    awaiting_content: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Label {
+    None,
+    /// Right after a block keyword, which may be followed by `: label`.
+    AfterBlock,
+    AfterColon,
+}
+
+/// Tracks unclosed brackets and unterminated statements so that the lines continuing them are
+/// indented past the line they started on.
+#[derive(Debug)]
+pub(crate) struct ContinuationTracker {
+    brackets: Vec<OpenBracket>,
+    statement_open: bool,
+    statement_level: usize,
+    line_level: usize,
+    label: Label,
+}
+
+impl ContinuationTracker {
+    pub(crate) fn new() -> Self {
+        Self {
+            brackets: Vec::new(),
+            statement_open: false,
+            statement_level: 0,
+            line_level: 0,
+            label: Label::None,
+        }
+    }
+
+    /// Indentation for a line starting with `token`, whose block level is `level`. Lines that
+    /// start a statement body or a block (`starts_statement`) never continue the previous one.
+    pub(crate) fn line_indent(
+        &self,
+        token: &Token,
+        level: usize,
+        starts_statement: bool,
+        config: &FormatConfig,
+    ) -> LineIndent {
+        if let Some(bracket) = self.brackets.last() {
+            if is_close_bracket(&token.text) {
+                return if config.align_to_open_paren && bracket.content_column.is_some() {
+                    LineIndent::Column {
+                        level: bracket.base_level,
+                        column: bracket.column,
+                    }
+                } else {
+                    LineIndent::Level(bracket.base_level)
+                };
+            }
+            return match bracket.content_column {
+                Some(column) if config.align_to_open_paren => LineIndent::Column {
+                    level: bracket.base_level,
+                    column,
+                },
+                _ => LineIndent::Level(bracket.base_level + config.continuation_indent),
+            };
+        }
+        if self.statement_open && !starts_statement && token.kind != TokenKind::Directive {
+            return LineIndent::Level(self.statement_level + config.continuation_indent);
+        }
+        LineIndent::Level(level)
+    }
+
+    /// Records the indentation chosen for the line being written.
+    pub(crate) fn start_line(&mut self, indent: LineIndent, starts_statement: bool) {
+        self.line_level = indent.level();
+        if starts_statement && self.brackets.is_empty() {
+            self.end_statement();
+        }
+    }
+
+    pub(crate) fn newline(&mut self) {
+        for bracket in &mut self.brackets {
+            bracket.awaiting_content = false;
+        }
+    }
+
+    /// Ends the current statement, as when a `begin`/`end` is inserted around it.
+    pub(crate) fn end_statement(&mut self) {
+        self.statement_open = false;
+        self.label = Label::None;
+    }
+
+    /// Updates the state after `token` was written starting at output column `column`
+    /// (`None` when the token was not laid out, as in verbatim regions).
+    pub(crate) fn observe(&mut self, token: &Token, blocks: &BlockKeywords, column: Option<usize>) {
+        if matches!(
+            token.kind,
+            TokenKind::Newline | TokenKind::Comment | TokenKind::Directive
+        ) {
+            return;
+        }
+        if let Some(bracket) = self.brackets.last_mut()
+            && bracket.awaiting_content
+        {
+            bracket.content_column = column;
+            bracket.awaiting_content = false;
+        }
+
+        let label = self.label;
+        self.label = Label::None;
+        if blocks.opens(token.offset) || blocks.closes(token.offset) {
+            self.statement_open = false;
+            self.label = Label::AfterBlock;
+            return;
+        }
+        match (label, token.text.as_str()) {
+            (Label::AfterBlock, ":") => {
+                self.label = Label::AfterColon;
+                return;
+            }
+            (Label::AfterColon, _) if token.is_identifier_like() => return,
+            _ => {}
+        }
+
+        if self.brackets.is_empty() && !self.statement_open {
+            self.statement_open = true;
+            self.statement_level = self.line_level;
+        }
+        let text = token.text.as_str();
+        if is_open_bracket(text) {
+            self.brackets.push(OpenBracket {
+                base_level: self.line_level,
+                column: column.unwrap_or(0),
+                content_column: None,
+                awaiting_content: column.is_some(),
+            });
+        } else if is_close_bracket(text) {
+            self.brackets.pop();
+            // Constraint, covergroup and similar `{ ... }` bodies are not followed by `;`.
+            if self.brackets.is_empty() && text == "}" {
+                self.statement_open = false;
+            }
+        } else if self.brackets.is_empty() && (text == ";" || is_macro_usage(token)) {
+            self.statement_open = false;
+        }
+    }
+}
+
+fn is_open_bracket(text: &str) -> bool {
+    matches!(text, "(" | "[" | "{" | "'{")
+}
+
+fn is_close_bracket(text: &str) -> bool {
+    matches!(text, ")" | "]" | "}")
+}
+
+/// Macro usages often stand for whole statements and carry no `;` of their own.
+fn is_macro_usage(token: &Token) -> bool {
+    token.kind == TokenKind::Other && token.text.starts_with('`')
+}
@@ -62,18 +62,43 @@ impl<'a> Emitter<'a> {
     }
 
     pub(crate) fn write_indent(&mut self) {
+        self.write_indent_at(self.indent_level);
+    }
+
+    /// Writes the indentation for `level` without changing the current indent level.
+    pub(crate) fn write_indent_at(&mut self, level: usize) {
         if self.config.use_tabs {
-            for _ in 0..self.indent_level {
+            for _ in 0..level {
                 self.output.push('\t');
             }
         } else {
-            self.output
-                .push_str(&" ".repeat(self.indent_level * self.config.indent_width));
+            self.output.push_str(&" ".repeat(level * self.config.indent_width));
         }
         self.at_line_start = false;
         self.pending_space = false;
     }
 
+    /// Indents to character `column` of a line indented to `level`: with tabs, the line's own
+    /// tabs are repeated and the rest is padded with spaces so the alignment survives any tab
+    /// width.
+    pub(crate) fn write_column(&mut self, level: usize, column: usize) {
+        if self.config.use_tabs {
+            let tabs = level.min(column);
+            self.output.push_str(&"\t".repeat(tabs));
+            self.output.push_str(&" ".repeat(column - tabs));
+        } else {
+            self.output.push_str(&" ".repeat(column));
+        }
+        self.at_line_start = false;
+        self.pending_space = false;
+    }
+
+    /// Number of characters written on the current line so far.
+    pub(crate) fn current_column(&self) -> usize {
+        let line_start = self.output.rfind('\n').map_or(0, |index| index + 1);
+        self.output[line_start..].chars().count()
+    }
+
     pub(crate) fn push_str(&mut self, text: &str) {
         self.output.push_str(text);
     }
@@ -1,36 +1,162 @@
-use std::collections::HashMap;
+use std::{
+    collections::{HashMap, HashSet},
+    ops::Range,
+};
 
 use anyhow::Result;
 
 use super::{
-    analysis::{ByteSpan, collect_case_alignment, collect_statement_spans},
+    analysis::{
+        AlignedList, BlockKeywords, ByteSpan, ListEntry, ListKind, case_alignment, collect_assignments, collect_blocks,
+        collect_case_labels, collect_connection_lists, collect_declarations, collect_item_starts,
+        collect_parameter_declarations, collect_parameter_lists, collect_port_lists, collect_statement_spans,
+    },
+    casing,
+    comments::align_trailing_comments,
+    continuation::{ContinuationTracker, LineIndent},
     emitter::Emitter,
+    fallback::{FormatOutput, format_units},
     lexer::{Token, TokenKind, tokenize},
+    lists::{ListStyle, RowStyle, align_rows, inline_width, layout_list},
+    origin::map_to_source,
+    pragma::pragma_regions,
     wrapping::wrap_formatted_output,
 };
 use crate::{
     config::FormatConfig,
-    parser::{self, SvParserCfg},
+    parser::{self, ParseError, SvParserCfg},
 };
 
 pub fn format_text(input: &str, config: &FormatConfig) -> Result<String> {
-    let tree = parser::parse(input, &SvParserCfg::default())?;
-    let body_spans = collect_statement_spans(&tree);
-    let case_alignment = collect_case_alignment(&tree);
-    let tokens = tokenize(&tree);
-    let mut formatter = Formatter::new(config, &tokens, body_spans, case_alignment);
+    format_document(input, config).map(|output| output.text)
+}
+
+/// Formats `input` and reports the regions that could only be formatted in whitespace-only
+/// mode because they failed to parse, including on missing include files and undefined macros.
+/// Such errors are returned as errors instead when `fallback_on_parse_error` is off.
+pub fn format_document(input: &str, config: &FormatConfig) -> Result<FormatOutput> {
+    match format_source(input, config, &[]) {
+        Ok(text) => Ok(FormatOutput {
+            text,
+            fallback_regions: Vec::new(),
+        }),
+        Err(err) if config.fallback_on_parse_error => match err.downcast::<ParseError>() {
+            Ok(parse_error) => Ok(format_units(input, config, parse_error)),
+            Err(err) => Err(err),
+        },
+        Err(err) => Err(err),
+    }
+}
+
+/// Formats `input`, copying the byte ranges in `verbatim` (sorted, non-overlapping) unchanged.
+pub(crate) fn format_source(input: &str, config: &FormatConfig, verbatim: &[Range<usize>]) -> Result<String> {
+    format_rewritten(input, input, config, verbatim)
+}
+
+/// Formats `rewritten`, a copy of `input` with some keywords lowercased, copying the regions
+/// left unformatted from `input` so they keep their case. Both texts have the same length.
+pub(crate) fn format_rewritten(
+    rewritten: &str,
+    input: &str,
+    config: &FormatConfig,
+    verbatim: &[Range<usize>],
+) -> Result<String> {
+    let parser_cfg = SvParserCfg {
+        // An incomplete parse stops at the first error and would silently drop the rest.
+        allow_incomplete: false,
+        defines: config.defines.clone(),
+        include_dirs: config.include_dirs.clone(),
+    };
+    let tree = parser::parse(rewritten, &parser_cfg)?;
+    let mapped = map_to_source(&tree, rewritten, tokenize(&tree));
+    let layout = Layout {
+        blocks: BlockKeywords::new(mapped.remap_blocks(collect_blocks(&tree))),
+        body_spans: mapped.remap_spans(collect_statement_spans(&tree)),
+        item_starts: mapped.remap_starts(collect_item_starts(&tree)),
+        case_alignment: case_alignment(mapped.remap_case_labels(collect_case_labels(&tree))),
+        lists: mapped.remap_lists(
+            [
+                collect_port_lists(&tree),
+                collect_parameter_lists(&tree),
+                collect_connection_lists(&tree),
+            ]
+            .concat(),
+        ),
+        parameter_rows: mapped.remap_entries(collect_parameter_declarations(&tree)),
+        assignment_rows: mapped.remap_entries(collect_assignments(&tree)),
+        declaration_rows: mapped.remap_entries(collect_declarations(&tree)),
+    };
+    let verbatim = merge_ranges(verbatim, &mapped.verbatim);
+    let verbatim = merge_ranges(&verbatim, &pragma_regions(rewritten, &mapped.tokens, &layout.blocks));
+    let mut formatter = Formatter::new(config, input, &mapped.tokens, layout, &verbatim, &mapped.verbatim);
     formatter.format()
 }
 
+/// What the syntax tree says about the layout, in source offsets.
+struct Layout {
+    blocks: BlockKeywords,
+    body_spans: HashMap<usize, ByteSpan>,
+    item_starts: Vec<usize>,
+    case_alignment: HashMap<usize, usize>,
+    lists: Vec<AlignedList>,
+    parameter_rows: Vec<ListEntry>,
+    assignment_rows: Vec<ListEntry>,
+    declaration_rows: Vec<ListEntry>,
+}
+
+fn hanging_bodies(body_spans: &HashMap<usize, ByteSpan>) -> HashMap<usize, usize> {
+    let mut bodies = HashMap::new();
+    for span in body_spans.values().filter(|span| !span.block) {
+        let end = bodies.entry(span.start).or_insert(span.end);
+        *end = (*end).max(span.end);
+    }
+    bodies
+}
+
+/// Combines two sorted lists of ranges into one sorted list without overlaps.
+fn merge_ranges(a: &[Range<usize>], b: &[Range<usize>]) -> Vec<Range<usize>> {
+    let mut all: Vec<Range<usize>> = a.iter().chain(b).cloned().collect();
+    all.sort_by_key(|range| range.start);
+    let mut merged: Vec<Range<usize>> = Vec::with_capacity(all.len());
+    for range in all {
+        match merged.last_mut() {
+            Some(last) if range.start <= last.end => last.end = last.end.max(range.end),
+            _ => merged.push(range),
+        }
+    }
+    merged
+}
+
 struct Formatter<'a> {
     config: &'a FormatConfig,
+    source: &'a str,
     tokens: &'a [Token],
+    verbatim: &'a [Range<usize>],
+    /// Inactive conditional branches, each a single token standing for no code at all.
+    inactive: &'a [Range<usize>],
+    blocks: BlockKeywords,
     body_spans: HashMap<usize, ByteSpan>,
+    /// End offset of every statement body that gets a hanging indent, keyed by its start.
+    hanging_bodies: HashMap<usize, usize>,
+    /// Start and end of the hanging bodies currently indented, innermost last.
+    active_hangs: Vec<(usize, usize)>,
+    /// Offsets of tokens that begin a statement even when the previous line left one open:
+    /// statement bodies and case items.
+    statement_starts: HashSet<usize>,
+    continuation: ContinuationTracker,
     case_alignment: HashMap<usize, usize>,
+    /// Port, parameter and connection lists, keyed by the offset of their opening parenthesis.
+    lists: HashMap<usize, AlignedList>,
+    /// Column, relative to the start of its line, that a token is padded to.
+    column_targets: HashMap<usize, usize>,
+    /// Output column where the code on the current line starts.
+    line_start_column: usize,
     idx: usize,
     emitter: Emitter<'a>,
     previous_call_ident: bool,
     inserted_blocks: Vec<usize>,
+    /// Output offsets of the `//` comments written after code on their line.
+    trailing_comments: Vec<usize>,
     wrap_tracker: WrapTracker,
 }
 
@@ -84,19 +210,61 @@ impl WrapKeyword {
 impl<'a> Formatter<'a> {
     fn new(
         config: &'a FormatConfig,
+        source: &'a str,
         tokens: &'a [Token],
-        body_spans: HashMap<usize, ByteSpan>,
-        case_alignment: HashMap<usize, usize>,
+        layout: Layout,
+        verbatim: &'a [Range<usize>],
+        inactive: &'a [Range<usize>],
     ) -> Self {
+        let Layout {
+            blocks,
+            body_spans,
+            item_starts,
+            case_alignment,
+            lists,
+            mut parameter_rows,
+            mut assignment_rows,
+            mut declaration_rows,
+        } = layout;
+        let hanging_bodies = hanging_bodies(&body_spans);
+        let mut column_targets = HashMap::new();
+        let declaration_style = RowStyle {
+            trailing_comments: true,
+            max_padding: Some(config.declaration_max_padding),
+        };
+        for (rows, align, style) in [
+            (&mut parameter_rows, config.align_parameters, RowStyle::default()),
+            (&mut assignment_rows, config.align_assignments, RowStyle::default()),
+            (&mut declaration_rows, config.align_declarations, declaration_style),
+        ] {
+            if align {
+                // A body hanging under `if (...)` and the like is indented past its neighbours.
+                rows.retain(|row| !hanging_bodies.contains_key(&row.start));
+                rows.sort_by_key(|row| row.start);
+                column_targets.extend(align_rows(rows, tokens, config, style));
+            }
+        }
         Self {
             config,
+            source,
             tokens,
+            verbatim,
+            inactive,
+            blocks,
+            hanging_bodies,
+            active_hangs: Vec::new(),
+            statement_starts: body_spans.values().map(|span| span.start).chain(item_starts).collect(),
+            continuation: ContinuationTracker::new(),
             body_spans,
             case_alignment,
+            lists: lists.into_iter().map(|list| (list.open, list)).collect(),
+            column_targets,
+            line_start_column: 0,
             idx: 0,
             emitter: Emitter::new(config),
             previous_call_ident: false,
             inserted_blocks: Vec::new(),
+            trailing_comments: Vec::new(),
             wrap_tracker: WrapTracker::new(),
         }
     }
@@ -104,6 +272,13 @@ impl<'a> Formatter<'a> {
     fn format(&mut self) -> Result<String> {
         while self.idx < self.tokens.len() {
             let token = &self.tokens[self.idx];
+            if let Some(region) = self.verbatim_region(token.offset) {
+                self.emit_verbatim(region);
+                continue;
+            }
+            if token.kind != TokenKind::Newline {
+                self.update_hanging_indent(token);
+            }
             match token.kind {
                 TokenKind::Newline => self.handle_newline(),
                 TokenKind::Comment => self.handle_comment(token),
@@ -114,7 +289,7 @@ impl<'a> Formatter<'a> {
         }
 
         if self.config.wrap_multiline_blocks {
-            while let Some(_) = self.inserted_blocks.pop() {
+            while self.inserted_blocks.pop().is_some() {
                 self.insert_auto_end();
             }
         }
@@ -122,6 +297,9 @@ impl<'a> Formatter<'a> {
         self.emitter.ensure_trailing_newline();
 
         let mut final_output = self.emitter.take_output();
+        if self.config.align_trailing_comments {
+            final_output = align_trailing_comments(final_output, &self.trailing_comments, self.config);
+        }
         if self.config.auto_wrap_long_lines && self.config.max_line_length > 0 {
             final_output = wrap_formatted_output(final_output, self.config);
         }
@@ -130,16 +308,18 @@ impl<'a> Formatter<'a> {
     }
 
     fn handle_newline(&mut self) {
-        if self.config.inline_end_else && self.prev_non_newline().map(|t| t.is_keyword("end")).unwrap_or(false) {
-            if let Some(next) = self.peek_non_newline() {
-                if next.is_keyword("else") {
-                    self.emitter.set_pending_space(true);
-                    return;
-                }
-            }
+        if self.config.inline_end_else
+            && self.prev_non_newline().map(|t| t.is_keyword("end")).unwrap_or(false)
+            && let Some(next) = self.peek_non_newline()
+            && next.is_keyword("else")
+            && self.verbatim_region(next.offset).is_none()
+        {
+            self.emitter.set_pending_space(true);
+            return;
         }
 
         self.emitter.newline();
+        self.continuation.newline();
         self.previous_call_ident = false;
 
         if self.config.wrap_multiline_blocks {
@@ -148,24 +328,199 @@ impl<'a> Formatter<'a> {
         }
     }
 
+    /// Ends the hanging indents of bodies that finished before `token`, and starts one when
+    /// `token` begins a line inside a single-statement body whose header is on an earlier line.
+    fn update_hanging_indent(&mut self, token: &Token) {
+        while let Some(&(_, end)) = self.active_hangs.last()
+            && end <= token.offset
+        {
+            self.active_hangs.pop();
+            self.emitter.decrease_indent();
+        }
+        if !self.emitter.at_line_start() {
+            return;
+        }
+        // Comments between a header and its body already belong to the body.
+        let body = if token.kind == TokenKind::Comment {
+            match self.tokens[self.idx..]
+                .iter()
+                .find(|tok| !matches!(tok.kind, TokenKind::Newline | TokenKind::Comment))
+            {
+                Some(next) => next.offset,
+                None => return,
+            }
+        } else {
+            token.offset
+        };
+        if let Some(&end) = self.hanging_bodies.get(&body)
+            && !self.active_hangs.iter().any(|&(start, _)| start == body)
+        {
+            self.active_hangs.push((body, end));
+            self.emitter.increase_indent();
+        }
+    }
+
+    /// Indents a line starting with `token`, continuing an unfinished statement or bracket.
+    fn write_line_indent(&mut self, token: &Token) {
+        // A comment is indented like the code it precedes.
+        let next = if token.kind == TokenKind::Comment {
+            self.tokens[self.idx..]
+                .iter()
+                .find(|tok| !matches!(tok.kind, TokenKind::Newline | TokenKind::Comment))
+        } else {
+            Some(token)
+        };
+        let starts_statement = next.is_none_or(|next| {
+            self.statement_starts.contains(&next.offset)
+                || self.blocks.opens(next.offset)
+                || self.blocks.closes(next.offset)
+        });
+        let indent = self.continuation.line_indent(
+            next.unwrap_or(token),
+            self.emitter.indent_level(),
+            starts_statement,
+            self.config,
+        );
+        self.write_indent_for(indent, starts_statement);
+    }
+
+    fn write_indent_for(&mut self, indent: LineIndent, starts_statement: bool) {
+        match indent {
+            LineIndent::Level(level) => self.emitter.write_indent_at(level),
+            LineIndent::Column { level, column } => self.emitter.write_column(level, column),
+        }
+        self.line_start_column = self.emitter.current_column();
+        self.continuation.start_line(indent, starts_statement);
+    }
+
+    /// Writes the entries of `list`, whose opening parenthesis was just written, one per line
+    /// and leaves the closing parenthesis to start the next line. Lists that cannot be laid
+    /// out are left to the token-by-token formatting.
+    fn emit_aligned_list(&mut self, list: &AlignedList) {
+        let style = ListStyle {
+            leading_commas: self.config.port_list_leading_commas,
+            align: match list.kind {
+                ListKind::Ports => self.config.align_port_list,
+                ListKind::Parameters => self.config.align_parameters,
+                ListKind::Connections => self.config.align_connections,
+            },
+        };
+        let Some(close) = self.tokens[self.idx..]
+            .iter()
+            .position(|token| token.offset == list.close)
+            .map(|index| self.idx + index)
+        else {
+            return;
+        };
+        let inner = &self.tokens[self.idx + 1..close];
+        if inner.iter().any(|token| self.verbatim_region(token.offset).is_some()) {
+            return;
+        }
+        let break_lines = match list.kind {
+            ListKind::Ports => self.config.port_list_per_line,
+            ListKind::Connections => self.config.connections_per_line,
+            ListKind::Parameters => {
+                (self.config.param_list_max_entries > 0 && list.entries.len() > self.config.param_list_max_entries)
+                    || inline_width(list, inner, self.config).is_none_or(|width| {
+                        // The list and its closing parenthesis.
+                        let end = self.emitter.current_column() + width + 1;
+                        self.config.max_line_length > 0 && end > self.config.max_line_length
+                    })
+            }
+        };
+        if !break_lines {
+            return;
+        }
+        let Some(lines) = layout_list(list, inner, self.config, style) else {
+            return;
+        };
+        let opening = &self.tokens[self.idx];
+        for line in lines {
+            self.emitter.newline();
+            self.continuation.newline();
+            let indent = self
+                .continuation
+                .line_indent(opening, self.emitter.indent_level(), false, self.config);
+            self.write_indent_for(indent, false);
+            self.emitter.push_str(&line);
+        }
+        self.emitter.newline();
+        self.continuation.newline();
+        self.emitter.set_last_line_was_comment(false);
+        self.previous_call_ident = false;
+        self.idx = close - 1;
+    }
+
+    fn verbatim_region(&self, offset: usize) -> Option<Range<usize>> {
+        self.verbatim.iter().find(|region| region.contains(&offset)).cloned()
+    }
+
+    /// Copies the source text of `region` unchanged and skips its tokens, keeping the indent
+    /// level in sync so formatted code after the region lines up with its context.
+    fn emit_verbatim(&mut self, region: Range<usize>) {
+        let start = match self.idx.checked_sub(1) {
+            Some(prev) => region.start.max(self.tokens[prev].offset + self.tokens[prev].len),
+            None => region.start,
+        };
+        let mut end = region.end;
+        self.continuation
+            .start_line(LineIndent::Level(self.emitter.indent_level()), false);
+        while self.idx < self.tokens.len() && self.tokens[self.idx].offset < end {
+            let token = &self.tokens[self.idx];
+            end = end.max(token.offset + token.len);
+            if self.blocks.closes(token.offset) {
+                self.emitter.decrease_indent();
+            }
+            if self.blocks.opens(token.offset) {
+                self.emitter.increase_indent();
+            }
+            if !self.inactive.iter().any(|branch| branch.start == token.offset) {
+                self.continuation.observe(token, &self.blocks, None);
+            }
+            self.idx += 1;
+        }
+        let end = end.min(self.source.len());
+        if start >= end {
+            return;
+        }
+
+        let starts_line = start == 0 || self.source[..start].ends_with('\n');
+        if starts_line && !self.emitter.buffer().is_empty() && !self.emitter.buffer().ends_with('\n') {
+            self.emitter.newline();
+        }
+        let text = &self.source[start..end];
+        self.emitter.push_str(text);
+        self.emitter.set_at_line_start(text.ends_with('\n'));
+        self.emitter.set_pending_space(false);
+        self.emitter.set_last_line_was_comment(false);
+        self.previous_call_ident = false;
+        self.wrap_tracker.reset();
+    }
+
     fn handle_comment(&mut self, token: &Token) {
         let text = token.text.trim_end_matches('\n');
         if text.trim_start().starts_with("/*") {
-            self.emit_block_comment(text);
+            self.emit_block_comment(token, text);
             return;
         }
-        self.emit_line_comment(text, token.text.contains('\n'));
+        self.emit_line_comment(token, text, token.text.contains('\n'));
     }
 
-    fn emit_line_comment(&mut self, text: &str, had_newline: bool) {
+    fn emit_line_comment(&mut self, token: &Token, text: &str, had_newline: bool) {
         if self.emitter.at_line_start() {
-            self.emitter.write_indent();
+            self.write_line_indent(token);
         } else {
             self.emitter.trim_trailing_whitespace();
             if self.emitter.buffer().ends_with('\n') {
-                self.emitter.write_indent();
+                self.write_line_indent(token);
             } else {
-                self.emitter.push_char(' ');
+                if let Some(&target) = self.column_targets.get(&token.offset) {
+                    let column = self.emitter.current_column().saturating_sub(self.line_start_column);
+                    self.emitter.push_str(&" ".repeat(target.saturating_sub(column).max(1)));
+                } else {
+                    self.emitter.push_char(' ');
+                }
+                self.trailing_comments.push(self.emitter.buffer().len());
             }
         }
         self.emitter.push_str(text);
@@ -180,9 +535,9 @@ impl<'a> Formatter<'a> {
         self.emitter.set_last_line_was_comment(true);
     }
 
-    fn emit_block_comment(&mut self, text: &str) {
+    fn emit_block_comment(&mut self, token: &Token, text: &str) {
         self.emitter.ensure_blank_line();
-        self.emitter.write_indent();
+        self.write_line_indent(token);
         self.emitter.push_str(text);
         self.emitter.push_char('\n');
         self.emitter.set_at_line_start(true);
@@ -210,7 +565,15 @@ impl<'a> Formatter<'a> {
         if !self.emitter.at_line_start() {
             self.emitter.newline();
         }
+        if !self.config.align_preprocessor {
+            self.write_line_indent(token);
+        }
         self.emitter.push_str(&token.text);
+        if token.text.ends_with('\\') {
+            // A `define continued onto a blank line ends there; removing that line would
+            // continue it onto the next one.
+            self.emitter.push_str("\n\n");
+        }
         self.emitter.set_at_line_start(false);
         self.emitter.set_pending_space(false);
         self.emitter.set_last_line_was_comment(false);
@@ -219,25 +582,28 @@ impl<'a> Formatter<'a> {
     fn handle_token(&mut self, token: &Token) {
         if self.config.wrap_multiline_blocks {
             self.flush_auto_ends_before(token);
-            self.wrap_tracker.observe_token(token);
+            self.wrap_tracker.observe_token(token, &self.blocks);
         }
 
-        if is_dedent_keyword(token) {
+        if self.blocks.closes(token.offset) {
             self.emitter.decrease_indent();
         }
 
-        if self.config.align_case_colon && token.text == ":" {
-            if self.apply_case_alignment(token) {
-                return;
-            }
+        if self.config.align_case_colon && token.text == ":" && self.apply_case_alignment(token) {
+            self.observe_continuation(token);
+            return;
         }
 
         if self.emitter.at_line_start() {
             self.maybe_insert_section_spacing(token);
-            self.emitter.write_indent();
-        } else if self.emitter.pending_space() && !needs_no_space_before(&token.text) {
+            self.write_line_indent(token);
+        } else if self.emitter.pending_space() && !needs_no_space_before(token, self.config) {
             self.emitter.push_char(' ');
         }
+        if let Some(&target) = self.column_targets.get(&token.offset) {
+            let column = self.emitter.current_column().saturating_sub(self.line_start_column);
+            self.emitter.push_str(&" ".repeat(target.saturating_sub(column)));
+        }
 
         if token.text == "," && self.config.space_after_comma {
             self.emitter.trim_trailing_whitespace();
@@ -248,12 +614,13 @@ impl<'a> Formatter<'a> {
             self.emitter.push_char('(');
             self.emitter.set_pending_space(false);
         } else {
-            self.emitter.push_str(&token.text);
-            let needs_space = needs_space_after(&token.text, self.peek_non_newline());
+            self.emitter.push_str(&casing::token_text(token, self.config));
+            let needs_space = needs_space_after(token, self.peek_non_newline(), self.config);
             self.emitter.set_pending_space(needs_space);
         }
+        self.observe_continuation(token);
 
-        if is_indent_keyword(token) {
+        if self.blocks.opens(token.offset) {
             self.emitter.increase_indent();
         }
 
@@ -265,6 +632,16 @@ impl<'a> Formatter<'a> {
             let span = self.body_spans.get(&token.offset).cloned();
             self.wrap_tracker.maybe_start(token, span);
         }
+
+        if let Some(list) = self.lists.remove(&token.offset) {
+            self.emit_aligned_list(&list);
+        }
+    }
+
+    /// Feeds the token just written to the continuation tracker.
+    fn observe_continuation(&mut self, token: &Token) {
+        let column = self.emitter.current_column().saturating_sub(token.text.chars().count());
+        self.continuation.observe(token, &self.blocks, Some(column));
     }
 
     fn apply_case_alignment(&mut self, token: &Token) -> bool {
@@ -306,13 +683,21 @@ impl<'a> Formatter<'a> {
             return;
         }
         if self.wrap_tracker.ready_to_wrap() {
-            if self.wrap_tracker.body_needs_wrap(&self.tokens, self.idx + 1) {
+            if self
+                .wrap_tracker
+                .body_needs_wrap(self.tokens, self.idx + 1, &self.blocks)
+            {
+                // The inserted block indents the body instead.
+                if let Some(span) = self.wrap_tracker.body_span {
+                    self.hanging_bodies.remove(&span.start);
+                }
                 self.emitter.write_indent();
                 self.emitter.push_str("begin");
                 self.emitter.push_char('\n');
                 self.emitter.increase_indent();
                 self.emitter.set_at_line_start(true);
                 self.emitter.set_pending_space(false);
+                self.continuation.end_statement();
                 self.inserted_blocks.push(self.emitter.indent_level());
             }
             self.wrap_tracker.reset();
@@ -326,7 +711,7 @@ impl<'a> Formatter<'a> {
         if self.inserted_blocks.is_empty() {
             return;
         }
-        if next.is_keyword("else") || is_dedent_keyword(next) {
+        if next.is_keyword("else") || self.blocks.closes(next.offset) {
             self.insert_auto_end();
             self.inserted_blocks.pop();
         }
@@ -347,8 +732,14 @@ impl<'a> Formatter<'a> {
     }
 }
 
-fn needs_space_after(text: &str, next: Option<&Token>) -> bool {
-    match text {
+pub(super) fn needs_space_after(token: &Token, next: Option<&Token>, config: &FormatConfig) -> bool {
+    // `##` is the prefix of a cycle delay such as `##1` or `##[1:3]`.
+    if config.space_around_operators
+        && (token.kind == TokenKind::UnaryOperator || matches!(token.text.as_str(), "::" | "##"))
+    {
+        return false;
+    }
+    match token.text.as_str() {
         "(" | "[" | "{" | "." | "@" => false,
         ")" | "]" | "}" | ";" | "," => true,
         ":" => matches!(next, Some(tok) if !tok.is_symbol(":")),
@@ -356,48 +747,17 @@ fn needs_space_after(text: &str, next: Option<&Token>) -> bool {
     }
 }
 
-fn needs_no_space_before(text: &str) -> bool {
-    matches!(text, ")" | "]" | "}" | "," | ";" | ".")
-}
-
-fn is_indent_keyword(token: &Token) -> bool {
-    token.is_keyword("module")
-        || token.is_keyword("class")
-        || token.is_keyword("function")
-        || token.is_keyword("task")
-        || token.is_keyword("package")
-        || token.is_keyword("begin")
-        || token.is_keyword("case")
-        || token.is_keyword("casex")
-        || token.is_keyword("casez")
-        || token.is_keyword("randcase")
-        || token.is_keyword("randsequence")
-        || token.is_keyword("covergroup")
-        || token.is_keyword("fork")
-        || token.is_keyword("generate")
-        || token.is_keyword("interface")
+pub(super) fn needs_no_space_before(token: &Token, config: &FormatConfig) -> bool {
+    if config.space_around_operators && (token.kind == TokenKind::PostfixOperator || token.text == "::") {
+        return true;
+    }
+    matches!(token.text.as_str(), ")" | "]" | "}" | "," | ";" | ".")
 }
 
 fn is_section_decl_keyword(token: &Token) -> bool {
     token.is_keyword("package") || token.is_keyword("class") || token.is_keyword("interface")
 }
 
-fn is_dedent_keyword(token: &Token) -> bool {
-    token.is_keyword("end")
-        || token.is_keyword("endmodule")
-        || token.is_keyword("endclass")
-        || token.is_keyword("endfunction")
-        || token.is_keyword("endtask")
-        || token.is_keyword("endcase")
-        || token.is_keyword("endsequence")
-        || token.is_keyword("endpackage")
-        || token.is_keyword("endgroup")
-        || token.is_keyword("endgenerate")
-        || token.is_keyword("join")
-        || token.is_keyword("join_any")
-        || token.is_keyword("join_none")
-}
-
 impl WrapTracker {
     fn new() -> Self {
         Self {
@@ -432,7 +792,7 @@ impl WrapTracker {
         }
     }
 
-    fn observe_token(&mut self, token: &Token) {
+    fn observe_token(&mut self, token: &Token, blocks: &BlockKeywords) {
         match self.mode {
             WrapMode::Idle => {}
             WrapMode::WaitingCondition => match token.text.as_str() {
@@ -448,7 +808,7 @@ impl WrapTracker {
                 _ => {}
             },
             WrapMode::Ready => {
-                if token.is_keyword("begin") || token.text == ";" || is_dedent_keyword(token) {
+                if token.is_keyword("begin") || token.text == ";" || blocks.closes(token.offset) {
                     self.reset();
                 }
             }
@@ -459,7 +819,7 @@ impl WrapTracker {
         matches!(self.mode, WrapMode::Ready)
     }
 
-    fn body_needs_wrap(&self, tokens: &[Token], index: usize) -> bool {
+    fn body_needs_wrap(&self, tokens: &[Token], index: usize, blocks: &BlockKeywords) -> bool {
         let keyword = match self.keyword {
             Some(k) => k,
             None => return false,
@@ -473,10 +833,10 @@ impl WrapTracker {
             if matches!(token.kind, TokenKind::Newline) {
                 continue;
             }
-            if let Some(end) = span_end {
-                if token.offset < end {
-                    continue;
-                }
+            if let Some(end) = span_end
+                && token.offset < end
+            {
+                continue;
             }
             if token.is_keyword("begin") {
                 return false;
@@ -484,7 +844,7 @@ impl WrapTracker {
             if matches!(keyword, WrapKeyword::Else) && token.is_keyword("if") {
                 return false;
             }
-            if token.is_keyword("else") || is_dedent_keyword(token) {
+            if token.is_keyword("else") || blocks.closes(token.offset) {
                 break;
             }
             if token.text == ";" {
@@ -0,0 +1,328 @@
+use std::ops::Range;
+
+use super::{
+    casing,
+    emitter::Emitter,
+    engine::{format_rewritten, format_source},
+    lexer::is_keyword,
+    pragma::{Pragma, parse_pragma},
+};
+use crate::{
+    config::{FormatConfig, KeywordCase},
+    parser::{ParseError, ParseErrorKind},
+};
+
+/// Result of [`format_document`](super::format_document).
+#[derive(Debug, Clone)]
+pub struct FormatOutput {
+    pub text: String,
+    /// Parts of the input that could not be parsed and only had their whitespace normalized.
+    pub fallback_regions: Vec<FallbackRegion>,
+}
+
+/// A run of lines formatted in whitespace-only mode because it failed to parse.
+#[derive(Debug, Clone)]
+pub struct FallbackRegion {
+    /// 1-based, inclusive line numbers in the input.
+    pub start_line: usize,
+    pub end_line: usize,
+    /// Why the region could not be parsed. Locations are relative to the whole input.
+    pub error: ParseError,
+}
+
+/// Formats `input` one design unit at a time after the whole file failed to parse with `error`.
+///
+/// Units that parse on their own are formatted normally; the rest are only re-indented from
+/// their block keywords and stripped of trailing whitespace. Units are parsed in isolation, so
+/// macros defined in an earlier unit of the same file are unknown to later ones.
+pub(crate) fn format_units(input: &str, config: &FormatConfig, error: ParseError) -> FormatOutput {
+    let units = design_units(input);
+    let mut output = FormatOutput {
+        text: String::with_capacity(input.len()),
+        fallback_regions: Vec::new(),
+    };
+    let mut error = Some(error);
+    for unit in &units {
+        let text = &input[unit.clone()];
+        let start_line = input[..unit.start].matches('\n').count() + 1;
+        let result = if units.len() == 1 {
+            Err(error.take().expect("a single unit is only formatted once"))
+        } else {
+            format_source(text, config, &[]).map_err(|err| match err.downcast::<ParseError>() {
+                Ok(parse_error) => parse_error,
+                Err(other) => ParseError {
+                    kind: ParseErrorKind::Other,
+                    message: format!("{other:#}"),
+                    location: None,
+                },
+            })
+        };
+        match result.or_else(|err| format_lowercased(text, config).ok_or(err)) {
+            Ok(formatted) => output.text.push_str(&formatted),
+            Err(mut unit_error) => {
+                if let Some(location) = unit_error.location.as_mut().filter(|location| location.file.is_none()) {
+                    location.line += start_line - 1;
+                }
+                output.text.push_str(&reindent(text, config));
+                let code = text.trim_end();
+                let leading_blank = code.len() - code.trim_start().len();
+                let first_line = start_line + code[..leading_blank].matches('\n').count();
+                output.fallback_regions.push(FallbackRegion {
+                    start_line: first_line,
+                    end_line: start_line + code.matches('\n').count(),
+                    error: unit_error,
+                });
+            }
+        }
+    }
+    output
+}
+
+/// Formats `text` with its keywords lowercased when `keyword_case = "lower"` and that makes it
+/// parse, as it does for legacy code written with `MODULE` and `BEGIN`. Words that only look
+/// like keywords, such as an identifier `Input`, keep the text from parsing and so their case.
+fn format_lowercased(text: &str, config: &FormatConfig) -> Option<String> {
+    if config.keyword_case != KeywordCase::Lower {
+        return None;
+    }
+    let lowered = casing::lowercase_keywords(text)?;
+    format_rewritten(&lowered, text, config, &[]).ok()
+}
+
+/// Splits `source` after every line that closes a top-level construct such as `endmodule`.
+fn design_units(source: &str) -> Vec<Range<usize>> {
+    let mut scanner = BlockScanner::default();
+    let mut units = Vec::new();
+    let mut start = 0;
+    let mut offset = 0;
+    for line in source.split_inclusive('\n') {
+        offset += line.len();
+        let info = scanner.scan(line);
+        if info.closed_top_level && !source[start..offset].trim().is_empty() {
+            units.push(start..offset);
+            start = offset;
+        }
+    }
+    if start < source.len() || units.is_empty() {
+        units.push(start..source.len());
+    }
+    units
+}
+
+/// Whitespace-only formatting: indentation from block keywords and no trailing whitespace.
+/// Everything else on a line, including blank lines, is kept as written, and lines between
+/// `// sv-fmt: off` and `// sv-fmt: on` or holding the statement after `// sv-fmt: skip` are
+/// not touched at all.
+pub(crate) fn reindent(text: &str, config: &FormatConfig) -> String {
+    let mut scanner = BlockScanner::default();
+    let mut emitter = Emitter::new(config);
+    let mut formatting_off = false;
+    let mut skip_pending = false;
+    // Depth at which the statement following a `skip` pragma started, while copying it.
+    let mut skip_depth = None;
+    for line in text.lines() {
+        let depth = scanner.depth;
+        let info = scanner.scan(line);
+        let trimmed = line.trim();
+        let pragma = parse_pragma(trimmed);
+        match pragma {
+            Some(Pragma::Off) => formatting_off = true,
+            Some(Pragma::On) if formatting_off => {
+                formatting_off = false;
+                emitter.push_str(line);
+                emitter.push_char('\n');
+                continue;
+            }
+            Some(Pragma::Skip) if !formatting_off && skip_depth.is_none() => skip_pending = true,
+            _ => {}
+        }
+        if skip_pending && pragma.is_none() && !info.in_comment && !trimmed.is_empty() && !trimmed.starts_with("//") {
+            skip_pending = false;
+            skip_depth = Some(depth);
+        }
+        if let Some(start) = skip_depth {
+            // Without a syntax tree, the skipped statement ends at a `;` or where the block it
+            // opens is closed, so a following `else` is formatted again.
+            emitter.push_str(line);
+            if scanner.depth <= start && (info.semicolon || scanner.depth < depth) {
+                skip_depth = None;
+            }
+        } else if formatting_off {
+            emitter.push_str(line);
+        } else if info.in_comment || info.in_define_body {
+            // Comment and macro bodies keep their own layout.
+            emitter.push_str(line.trim_end());
+        } else if !trimmed.is_empty() {
+            let level = if info.leading_close {
+                depth.saturating_sub(1)
+            } else {
+                depth
+            };
+            while emitter.indent_level() < level {
+                emitter.increase_indent();
+            }
+            while emitter.indent_level() > level {
+                emitter.decrease_indent();
+            }
+            if !(config.align_preprocessor && trimmed.starts_with('`')) {
+                emitter.write_indent();
+            }
+            emitter.push_str(trimmed);
+        }
+        emitter.push_char('\n');
+    }
+    emitter.take_output()
+}
+
+/// Tracks block nesting in raw source text, one line at a time.
+#[derive(Default)]
+struct BlockScanner {
+    depth: usize,
+    in_comment: bool,
+    /// Inside a declaration without a body (`extern function ...;`), up to its `;`.
+    prototype: bool,
+    /// The previous line was a `` `define `` ending in a line continuation.
+    in_define: bool,
+    previous: String,
+}
+
+struct LineInfo {
+    /// The line starts inside a block comment.
+    in_comment: bool,
+    /// The line continues a multi-line `` `define ``.
+    in_define_body: bool,
+    /// The first word on the line closes a block.
+    leading_close: bool,
+    /// A block closed on this line brought the depth back to zero.
+    closed_top_level: bool,
+    /// The line holds a `;` outside comments and strings.
+    semicolon: bool,
+}
+
+impl BlockScanner {
+    fn scan(&mut self, line: &str) -> LineInfo {
+        let mut info = LineInfo {
+            in_comment: self.in_comment,
+            in_define_body: self.in_define,
+            leading_close: false,
+            closed_top_level: false,
+            semicolon: false,
+        };
+        let trimmed = line.trim();
+        if self.in_define || (!self.in_comment && trimmed.starts_with("`define")) {
+            // Macro bodies are not expanded here, so their keywords do not nest.
+            self.in_define = trimmed.ends_with('\\');
+            return info;
+        }
+        let mut first = true;
+        let bytes = line.as_bytes();
+        let mut pos = 0;
+        while pos < bytes.len() {
+            if self.in_comment {
+                match line[pos..].find("*/") {
+                    Some(index) => {
+                        self.in_comment = false;
+                        pos += index + 2;
+                    }
+                    None => break,
+                }
+                continue;
+            }
+            let byte = bytes[pos];
+            match byte {
+                b'/' if bytes.get(pos + 1) == Some(&b'/') => break,
+                b'/' if bytes.get(pos + 1) == Some(&b'*') => {
+                    self.in_comment = true;
+                    pos += 2;
+                    continue;
+                }
+                _ if byte.is_ascii_whitespace() => {
+                    pos += 1;
+                    continue;
+                }
+                b'"' => pos = string_end(bytes, pos),
+                b'`' | b'$' => pos = word_end(bytes, pos + 1),
+                b'\\' => {
+                    pos = line[pos..]
+                        .find(char::is_whitespace)
+                        .map_or(bytes.len(), |index| pos + index)
+                }
+                b';' => {
+                    self.prototype = false;
+                    info.semicolon = true;
+                    pos += 1;
+                }
+                _ if byte.is_ascii_alphabetic() || byte == b'_' => {
+                    let end = word_end(bytes, pos);
+                    let word = line[pos..end].to_ascii_lowercase();
+                    if closes_block(&word) {
+                        info.leading_close |= first;
+                        self.depth = self.depth.saturating_sub(1);
+                        info.closed_top_level |= self.depth == 0;
+                    } else if self.opens_block(&word) {
+                        self.depth += 1;
+                    } else if matches!(word.as_str(), "extern" | "pure" | "typedef" | "import" | "export") {
+                        self.prototype = true;
+                    }
+                    self.previous = word;
+                    pos = end;
+                }
+                _ if byte.is_ascii_digit() || byte == b'\'' => {
+                    pos += 1;
+                    while pos < bytes.len()
+                        && matches!(bytes[pos], b'0'..=b'9' | b'a'..=b'z' | b'A'..=b'Z' | b'_' | b'\'' | b'?')
+                    {
+                        pos += 1;
+                    }
+                }
+                _ => pos += 1,
+            }
+            first = false;
+        }
+        info
+    }
+
+    fn opens_block(&self, word: &str) -> bool {
+        let previous = self.previous.as_str();
+        match word {
+            // `virtual interface` is a type, `interface class` closes with a single `endclass`
+            // and `with function sample` declares a covergroup's sampling signature.
+            "function" | "task" | "class" | "module" | "interface" | "program" | "package" | "checker" => {
+                !self.prototype
+                    && !matches!(
+                        (previous, word),
+                        ("virtual", "interface") | ("interface", "class") | ("with", "function")
+                    )
+            }
+            "fork" => !matches!(previous, "disable" | "wait"),
+            "property" | "sequence" => !matches!(previous, "assert" | "assume" | "cover" | "restrict" | "expect"),
+            "macromodule" | "config" | "primitive" | "table" | "specify" | "generate" | "begin" | "case" | "casex"
+            | "casez" | "randcase" | "randsequence" | "covergroup" | "clocking" => true,
+            _ => false,
+        }
+    }
+}
+
+/// Every `end…` keyword closes a block, as do the `join` keywords closing a `fork`.
+fn closes_block(word: &str) -> bool {
+    (word.starts_with("end") || word.starts_with("join")) && is_keyword(word)
+}
+
+fn word_end(bytes: &[u8], pos: usize) -> usize {
+    bytes[pos..]
+        .iter()
+        .position(|&byte| !(byte.is_ascii_alphanumeric() || byte == b'_' || byte == b'$'))
+        .map_or(bytes.len(), |index| pos + index)
+}
+
+fn string_end(bytes: &[u8], pos: usize) -> usize {
+    let mut index = pos + 1;
+    while index < bytes.len() {
+        match bytes[index] {
+            b'\\' => index += 2,
+            b'"' => return index + 1,
+            _ => index += 1,
+        }
+    }
+    bytes.len()
+}
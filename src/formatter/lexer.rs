@@ -8,6 +8,12 @@ pub(crate) enum TokenKind {
     Keyword,
     Identifier,
     Symbol,
+    /// A prefix operator such as `!`, `~`, unary `-` or `++i`, written against its operand.
+    UnaryOperator,
+    /// An operator between two operands, such as `+`, `==` or `&&`.
+    BinaryOperator,
+    /// A `++` or `--` after its operand.
+    PostfixOperator,
     Number,
     StringLiteral,
     Comment,
@@ -48,10 +54,18 @@ impl Token {
 }
 
 pub(crate) fn tokenize(tree: &SyntaxTree) -> Vec<Token> {
-    let mut tokens = Vec::new();
+    let mut tokens: Vec<Token> = Vec::new();
     let mut whitespace_depth = 0usize;
     let mut comment_depth = 0usize;
     let mut directive_depth = 0usize;
+    let mut operator = None;
+    // Integral literals and identifiers keep their kind whatever their letters spell.
+    let mut literal_depth = 0usize;
+    // Index of the first token of the outermost literal being read.
+    let mut literal_start = 0;
+    let mut identifier_depth = 0usize;
+    // Whether each enclosing `++`/`--` expression puts the operator after its operand.
+    let mut inc_or_dec_suffix = Vec::new();
 
     for event in tree.into_iter().event() {
         match event {
@@ -59,14 +73,59 @@ pub(crate) fn tokenize(tree: &SyntaxTree) -> Vec<Token> {
                 RefNode::WhiteSpace(_) => whitespace_depth += 1,
                 RefNode::Comment(_) => comment_depth += 1,
                 RefNode::CompilerDirective(_) => directive_depth += 1,
+                RefNode::UnaryOperator(_) | RefNode::UnaryModulePathOperator(_) => {
+                    operator = Some(TokenKind::UnaryOperator)
+                }
+                RefNode::BinaryOperator(_) | RefNode::BinaryModulePathOperator(_) => {
+                    operator = Some(TokenKind::BinaryOperator)
+                }
+                RefNode::IntegralNumber(_) | RefNode::UnbasedUnsizedLiteral(_) => {
+                    if literal_depth == 0 {
+                        literal_start = tokens.len();
+                    }
+                    literal_depth += 1;
+                }
+                RefNode::SimpleIdentifier(_) | RefNode::EscapedIdentifier(_) => identifier_depth += 1,
+                RefNode::IncOrDecExpressionPrefix(_) | RefNode::GenvarIterationPrefix(_) => {
+                    inc_or_dec_suffix.push(false)
+                }
+                RefNode::IncOrDecExpressionSuffix(_) | RefNode::GenvarIterationSuffix(_) => {
+                    inc_or_dec_suffix.push(true)
+                }
+                RefNode::IncOrDecOperator(_) => {
+                    operator = Some(if inc_or_dec_suffix.last() == Some(&true) {
+                        TokenKind::PostfixOperator
+                    } else {
+                        TokenKind::UnaryOperator
+                    })
+                }
                 RefNode::Locate(loc) => {
-                    if let Some(text) = tree.get_str(loc) {
+                    let Some(text) = tree.get_str(loc) else {
+                        continue;
+                    };
+                    // The size, base and value of a literal such as `8'hFF` are separate nodes;
+                    // written without whitespace, they stay one token.
+                    if literal_depth > 0
+                        && whitespace_depth == 0
+                        && comment_depth == 0
+                        && tokens.len() > literal_start
+                        && let Some(last) = tokens.last_mut()
+                        && last.kind == TokenKind::Number
+                        && last.offset + last.len == loc.offset
+                    {
+                        last.text.push_str(text);
+                        last.len += text.len();
+                    } else {
+                        let kind = operator
+                            .or((literal_depth > 0).then_some(TokenKind::Number))
+                            .or((identifier_depth > 0).then_some(TokenKind::Identifier));
                         handle_locate(
                             text,
                             loc.offset,
                             whitespace_depth,
                             comment_depth,
                             directive_depth,
+                            kind,
                             &mut tokens,
                         );
                     }
@@ -77,6 +136,23 @@ pub(crate) fn tokenize(tree: &SyntaxTree) -> Vec<Token> {
                 RefNode::WhiteSpace(_) => whitespace_depth = whitespace_depth.saturating_sub(1),
                 RefNode::Comment(_) => comment_depth = comment_depth.saturating_sub(1),
                 RefNode::CompilerDirective(_) => directive_depth = directive_depth.saturating_sub(1),
+                RefNode::UnaryOperator(_)
+                | RefNode::UnaryModulePathOperator(_)
+                | RefNode::BinaryOperator(_)
+                | RefNode::BinaryModulePathOperator(_)
+                | RefNode::IncOrDecOperator(_) => operator = None,
+                RefNode::IntegralNumber(_) | RefNode::UnbasedUnsizedLiteral(_) => {
+                    literal_depth = literal_depth.saturating_sub(1)
+                }
+                RefNode::SimpleIdentifier(_) | RefNode::EscapedIdentifier(_) => {
+                    identifier_depth = identifier_depth.saturating_sub(1)
+                }
+                RefNode::IncOrDecExpressionPrefix(_)
+                | RefNode::IncOrDecExpressionSuffix(_)
+                | RefNode::GenvarIterationPrefix(_)
+                | RefNode::GenvarIterationSuffix(_) => {
+                    inc_or_dec_suffix.pop();
+                }
                 _ => {}
             },
         }
@@ -91,6 +167,7 @@ fn handle_locate(
     whitespace_depth: usize,
     comment_depth: usize,
     directive_depth: usize,
+    kind: Option<TokenKind>,
     tokens: &mut Vec<Token>,
 ) {
     if text.is_empty() {
@@ -115,12 +192,18 @@ fn handle_locate(
         return;
     }
 
-    tokens.push(Token::new_spanned(text, classify_token(text), offset, text.len()));
+    // Operator nodes also hold the whitespace after the symbol, which was handled above.
+    let kind = kind.unwrap_or_else(|| classify_token(text));
+    tokens.push(Token::new_spanned(text, kind, offset, text.len()));
+}
+
+/// Whether `word` spells a SystemVerilog keyword in any letter case.
+pub(crate) fn is_keyword(word: &str) -> bool {
+    KEYWORDS.contains(word.to_ascii_lowercase().as_str())
 }
 
 fn classify_token(text: &str) -> TokenKind {
-    let lowered = text.to_ascii_lowercase();
-    if KEYWORDS.contains(lowered.as_str()) {
+    if BLOCK_KEYWORDS.contains(text.to_ascii_lowercase().as_str()) {
         TokenKind::Keyword
     } else if is_identifier(text) {
         TokenKind::Identifier
@@ -128,7 +211,7 @@ fn classify_token(text: &str) -> TokenKind {
         TokenKind::Number
     } else if is_string_literal(text) {
         TokenKind::StringLiteral
-    } else if text.len() == 1 && is_symbol_char(text.chars().next().unwrap()) {
+    } else if (text.len() == 1 && is_symbol_char(text.chars().next().unwrap())) || OPERATORS.contains(text) {
         TokenKind::Symbol
     } else {
         TokenKind::Other
@@ -193,7 +276,20 @@ fn is_symbol_char(ch: char) -> bool {
     )
 }
 
-static KEYWORDS: Lazy<HashSet<&'static str>> = Lazy::new(|| {
+/// Symbols of more than one character, each lexed as a single token.
+static OPERATORS: Lazy<HashSet<&'static str>> = Lazy::new(|| {
+    [
+        "<=", ">=", "==", "!=", "===", "!==", "==?", "!=?", "&&", "||", "&&&", "**", "<<", ">>", "<<<", ">>>", "->",
+        "<->", "->>", "|->", "|=>", "=>", "*>", "::", "++", "--", "+=", "-=", "*=", "/=", "%=", "&=", "|=", "^=",
+        "<<=", ">>=", "<<<=", ">>>=", "~&", "~|", "~^", "^~", "+:", "-:", "##", ".*", ":=", ":/", "#-#", "#=#",
+    ]
+    .into_iter()
+    .collect()
+});
+
+/// Keywords that shape the layout, lexed as [`TokenKind::Keyword`]. Other keywords are lexed as
+/// identifiers.
+static BLOCK_KEYWORDS: Lazy<HashSet<&'static str>> = Lazy::new(|| {
     [
         "module",
         "endmodule",
@@ -235,3 +331,259 @@ static KEYWORDS: Lazy<HashSet<&'static str>> = Lazy::new(|| {
     .into_iter()
     .collect()
 });
+
+/// The keywords of IEEE 1800-2017, Annex B.
+static KEYWORDS: Lazy<HashSet<&'static str>> = Lazy::new(|| {
+    [
+        "accept_on",
+        "alias",
+        "always",
+        "always_comb",
+        "always_ff",
+        "always_latch",
+        "and",
+        "assert",
+        "assign",
+        "assume",
+        "automatic",
+        "before",
+        "begin",
+        "bind",
+        "bins",
+        "binsof",
+        "bit",
+        "break",
+        "buf",
+        "bufif0",
+        "bufif1",
+        "byte",
+        "case",
+        "casex",
+        "casez",
+        "cell",
+        "chandle",
+        "checker",
+        "class",
+        "clocking",
+        "cmos",
+        "config",
+        "const",
+        "constraint",
+        "context",
+        "continue",
+        "cover",
+        "covergroup",
+        "coverpoint",
+        "cross",
+        "deassign",
+        "default",
+        "defparam",
+        "design",
+        "disable",
+        "dist",
+        "do",
+        "edge",
+        "else",
+        "end",
+        "endcase",
+        "endchecker",
+        "endclass",
+        "endclocking",
+        "endconfig",
+        "endfunction",
+        "endgenerate",
+        "endgroup",
+        "endinterface",
+        "endmodule",
+        "endpackage",
+        "endprimitive",
+        "endprogram",
+        "endproperty",
+        "endspecify",
+        "endsequence",
+        "endtable",
+        "endtask",
+        "enum",
+        "event",
+        "eventually",
+        "expect",
+        "export",
+        "extends",
+        "extern",
+        "final",
+        "first_match",
+        "for",
+        "force",
+        "foreach",
+        "forever",
+        "fork",
+        "forkjoin",
+        "function",
+        "generate",
+        "genvar",
+        "global",
+        "highz0",
+        "highz1",
+        "if",
+        "iff",
+        "ifnone",
+        "ignore_bins",
+        "illegal_bins",
+        "implements",
+        "implies",
+        "import",
+        "incdir",
+        "include",
+        "initial",
+        "inout",
+        "input",
+        "inside",
+        "instance",
+        "int",
+        "integer",
+        "interconnect",
+        "interface",
+        "intersect",
+        "join",
+        "join_any",
+        "join_none",
+        "large",
+        "let",
+        "liblist",
+        "library",
+        "local",
+        "localparam",
+        "logic",
+        "longint",
+        "macromodule",
+        "matches",
+        "medium",
+        "modport",
+        "module",
+        "nand",
+        "negedge",
+        "nettype",
+        "new",
+        "nexttime",
+        "nmos",
+        "nor",
+        "noshowcancelled",
+        "not",
+        "notif0",
+        "notif1",
+        "null",
+        "or",
+        "output",
+        "package",
+        "packed",
+        "parameter",
+        "pmos",
+        "posedge",
+        "primitive",
+        "priority",
+        "program",
+        "property",
+        "protected",
+        "pull0",
+        "pull1",
+        "pulldown",
+        "pullup",
+        "pulsestyle_ondetect",
+        "pulsestyle_onevent",
+        "pure",
+        "rand",
+        "randc",
+        "randcase",
+        "randsequence",
+        "rcmos",
+        "real",
+        "realtime",
+        "ref",
+        "reg",
+        "reject_on",
+        "release",
+        "repeat",
+        "restrict",
+        "return",
+        "rnmos",
+        "rpmos",
+        "rtran",
+        "rtranif0",
+        "rtranif1",
+        "s_always",
+        "s_eventually",
+        "s_nexttime",
+        "s_until",
+        "s_until_with",
+        "scalared",
+        "sequence",
+        "shortint",
+        "shortreal",
+        "showcancelled",
+        "signed",
+        "small",
+        "soft",
+        "solve",
+        "specify",
+        "specparam",
+        "static",
+        "string",
+        "strong",
+        "strong0",
+        "strong1",
+        "struct",
+        "super",
+        "supply0",
+        "supply1",
+        "sync_accept_on",
+        "sync_reject_on",
+        "table",
+        "tagged",
+        "task",
+        "this",
+        "throughout",
+        "time",
+        "timeprecision",
+        "timeunit",
+        "tran",
+        "tranif0",
+        "tranif1",
+        "tri",
+        "tri0",
+        "tri1",
+        "triand",
+        "trior",
+        "trireg",
+        "type",
+        "typedef",
+        "union",
+        "unique",
+        "unique0",
+        "unsigned",
+        "until",
+        "until_with",
+        "untyped",
+        "use",
+        "uwire",
+        "var",
+        "vectored",
+        "virtual",
+        "void",
+        "wait",
+        "wait_order",
+        "wand",
+        "weak",
+        "weak0",
+        "weak1",
+        "while",
+        "wildcard",
+        "wire",
+        "with",
+        "within",
+        "wor",
+        "xnor",
+        "xor",
+    ]
+    .into_iter()
+    .collect()
+});
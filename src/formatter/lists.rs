@@ -0,0 +1,361 @@
+use std::collections::HashMap;
+
+use super::{
+    analysis::{AlignedList, ListEntry},
+    casing,
+    engine::{needs_no_space_before, needs_space_after},
+    lexer::{Token, TokenKind},
+};
+use crate::config::FormatConfig;
+
+/// How the entries of an [`AlignedList`] are written.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct ListStyle {
+    /// Put the comma separating two entries at the start of the second one's line.
+    pub(crate) leading_commas: bool,
+    /// Pad every column to its widest entry and line up trailing comments.
+    pub(crate) align: bool,
+}
+
+enum Line<'t> {
+    Comment(String),
+    Entry {
+        /// Tokens of each column.
+        cells: Vec<Vec<&'t Token>>,
+        comment: Option<String>,
+    },
+}
+
+/// Lays out `list` one entry per line. `tokens` are the tokens between its brackets; the
+/// returned lines carry no indentation. Returns `None` when the list holds something that
+/// cannot be moved between lines safely, such as a directive or a block comment.
+pub(crate) fn layout_list(
+    list: &AlignedList,
+    tokens: &[Token],
+    config: &FormatConfig,
+    style: ListStyle,
+) -> Option<Vec<String>> {
+    Some(render_lines(collect_lines(list, tokens)?, config, style))
+}
+
+/// Width of `list` written on a single line, or `None` when comments keep it from being
+/// joined.
+pub(crate) fn inline_width(list: &AlignedList, tokens: &[Token], config: &FormatConfig) -> Option<usize> {
+    let mut width = 0;
+    for line in collect_lines(list, tokens)? {
+        match line {
+            Line::Entry { cells, comment: None } => {
+                width += render_tokens(&cells.concat(), config).chars().count();
+            }
+            _ => return None,
+        }
+    }
+    Some(width + 2 * list.entries.len().saturating_sub(1))
+}
+
+/// How [`align_rows`] groups and pads rows.
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct RowStyle {
+    /// Also line up the `//` comments that end the rows.
+    pub(crate) trailing_comments: bool,
+    /// Start a new group rather than pad a column of any row by more than this many spaces.
+    pub(crate) max_padding: Option<usize>,
+}
+
+/// A row of [`align_rows`] written on a line of its own.
+struct Row<'t> {
+    /// First token and rendered width of each column.
+    cells: Vec<(Option<&'t Token>, usize)>,
+    /// Width of the `;` between the row and its trailing comment.
+    tail: usize,
+    comment: Option<&'t Token>,
+}
+
+/// Target columns, relative to the start of the line, for the tokens that start the columns
+/// of `rows`. Rows are aligned with the rows written on the lines right before and after them,
+/// such as a run of `localparam` declarations; rows spanning several lines or sharing them
+/// with other code are left alone.
+pub(crate) fn align_rows(
+    rows: &[ListEntry],
+    tokens: &[Token],
+    config: &FormatConfig,
+    style: RowStyle,
+) -> HashMap<usize, usize> {
+    let mut targets = HashMap::new();
+    let mut group: Vec<Row> = Vec::new();
+    let mut previous_end = None;
+    for row in rows {
+        let first = tokens.partition_point(|token| token.offset < row.start);
+        let last = tokens.partition_point(|token| token.offset < row.end);
+        let row_tokens = &tokens[first..last];
+        let tail = tokens[last..].iter().take_while(|token| token.text == ";").count();
+        let after = tokens.get(last + tail);
+        let own_line = (first == 0 || ends_line(&tokens[first - 1])) && after.is_none_or(ends_line);
+        let single_line = own_line
+            && row_tokens.iter().all(|token| {
+                !matches!(
+                    token.kind,
+                    TokenKind::Newline | TokenKind::Comment | TokenKind::Directive
+                )
+            });
+        let row = single_line.then(|| Row {
+            cells: column_cells(row, row_tokens)
+                .iter()
+                .map(|cell| (cell.first().copied(), render_tokens(cell, config).chars().count()))
+                .collect(),
+            tail,
+            comment: after.filter(|token| style.trailing_comments && token.kind == TokenKind::Comment),
+        });
+        let adjacent = previous_end.is_some_and(|end| next_line(&tokens[end..first]));
+        let fits = row.as_ref().is_some_and(|row| {
+            style
+                .max_padding
+                .is_none_or(|max_padding| padding(&group, row) <= max_padding)
+        });
+        if !(adjacent && fits) {
+            align_group(&group, &mut targets);
+            group.clear();
+        }
+        previous_end = row.is_some().then_some(last);
+        group.extend(row);
+    }
+    align_group(&group, &mut targets);
+    targets
+}
+
+fn ends_line(token: &Token) -> bool {
+    match token.kind {
+        TokenKind::Newline => true,
+        TokenKind::Comment => token.text.starts_with("//") && token.text.contains('\n'),
+        _ => false,
+    }
+}
+
+/// The tokens between two rows only end the first one and move to a later line. Blank lines do
+/// not count, as the formatter removes them; comments on lines of their own do.
+fn next_line(between: &[Token]) -> bool {
+    let mut line_breaks = 0;
+    for token in between {
+        match token.kind {
+            TokenKind::Newline => line_breaks += 1,
+            TokenKind::Comment if token.text.starts_with("//") && line_breaks == 0 => {
+                line_breaks += usize::from(ends_line(token))
+            }
+            _ if token.text == ";" => {}
+            _ => return false,
+        }
+    }
+    line_breaks >= 1
+}
+
+fn column_widths<'r, 't: 'r>(rows: impl IntoIterator<Item = &'r Row<'t>>) -> Vec<usize> {
+    let mut widths = Vec::new();
+    for row in rows {
+        widths.resize(widths.len().max(row.cells.len()), 0);
+        for (width, &(_, cell)) in widths.iter_mut().zip(&row.cells) {
+            *width = (*width).max(cell);
+        }
+    }
+    widths
+}
+
+/// The most spaces any column of `group` would be padded by once `row` joins it.
+fn padding(group: &[Row], row: &Row) -> usize {
+    let widths = column_widths(group.iter().chain([row]));
+    group
+        .iter()
+        .chain([row])
+        .flat_map(|row| row.cells.iter().zip(&widths))
+        .filter(|&(_, &width)| width > 0)
+        // An empty column is padded by its separator too.
+        .map(|(&(_, cell), &width)| if cell == 0 { width + 1 } else { width - cell })
+        .max()
+        .unwrap_or(0)
+}
+
+fn align_group(group: &[Row], targets: &mut HashMap<usize, usize>) {
+    if group.len() < 2 {
+        return;
+    }
+    let widths = column_widths(group);
+    let mut code_width = 0;
+    for row in group {
+        let mut column = 0;
+        let mut end = 0;
+        for (index, (&(token, cell), &width)) in row.cells.iter().zip(&widths).enumerate() {
+            if width == 0 {
+                continue;
+            }
+            if let Some(token) = token {
+                if index > 0 {
+                    targets.insert(token.offset, column);
+                }
+                end = column + cell;
+            }
+            column += width + 1;
+        }
+        code_width = code_width.max(end + row.tail);
+    }
+    for comment in group.iter().filter_map(|row| row.comment) {
+        targets.insert(comment.offset, code_width + 1);
+    }
+}
+
+/// Splits the tokens of `entry` into its columns. Tokens before its first column join it.
+fn column_cells<'t>(entry: &ListEntry, tokens: &'t [Token]) -> Vec<Vec<&'t Token>> {
+    let mut cells = vec![Vec::new(); entry.columns.len()];
+    for token in tokens {
+        cells[column_of(entry, token.offset)].push(token);
+    }
+    cells
+}
+
+fn column_of(entry: &ListEntry, offset: usize) -> usize {
+    entry
+        .columns
+        .iter()
+        .rposition(|start| start.is_some_and(|start| start <= offset))
+        .or_else(|| entry.columns.iter().position(Option::is_some))
+        .unwrap_or(0)
+}
+
+fn collect_lines<'t>(list: &AlignedList, tokens: &'t [Token]) -> Option<Vec<Line<'t>>> {
+    let mut lines = Vec::new();
+    let mut next_entry = 0;
+    let mut current = None;
+    let mut same_line = false;
+    for token in tokens {
+        match token.kind {
+            TokenKind::Newline => same_line = false,
+            TokenKind::Directive => return None,
+            TokenKind::Comment => {
+                let text = token.text.trim_end();
+                if text.starts_with("/*") {
+                    return None;
+                }
+                match lines.last_mut() {
+                    Some(Line::Entry {
+                        comment: comment @ None,
+                        ..
+                    }) if same_line => {
+                        *comment = Some(text.to_string());
+                    }
+                    _ => lines.push(Line::Comment(text.to_string())),
+                }
+                same_line = false;
+            }
+            _ => {
+                same_line = true;
+                if let Some(entry) = list.entries.get(next_entry)
+                    && entry.start == token.offset
+                {
+                    current = Some(entry);
+                    next_entry += 1;
+                    lines.push(Line::Entry {
+                        cells: vec![Vec::new(); entry.columns.len()],
+                        comment: None,
+                    });
+                }
+                match current {
+                    Some(entry) if token.offset < entry.end => {
+                        let column = column_of(entry, token.offset);
+                        let Some(Line::Entry { cells, .. }) =
+                            lines.iter_mut().rfind(|line| matches!(line, Line::Entry { .. }))
+                        else {
+                            return None;
+                        };
+                        cells[column].push(token);
+                    }
+                    _ if token.text == "," => {}
+                    _ => return None,
+                }
+            }
+        }
+    }
+    (next_entry == list.entries.len()).then_some(lines)
+}
+
+fn render_lines(lines: Vec<Line>, config: &FormatConfig, style: ListStyle) -> Vec<String> {
+    let entries: Vec<Vec<String>> = lines
+        .iter()
+        .filter_map(|line| match line {
+            Line::Entry { cells, .. } if style.align => {
+                Some(cells.iter().map(|cell| render_tokens(cell, config)).collect())
+            }
+            // Without alignment the entry is written as one run of tokens.
+            Line::Entry { cells, .. } => Some(vec![render_tokens(&cells.concat(), config)]),
+            Line::Comment(_) => None,
+        })
+        .collect();
+    let mut widths = vec![0; entries.first().map_or(0, Vec::len)];
+    for cells in &entries {
+        for (width, cell) in widths.iter_mut().zip(cells) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+    let codes: Vec<String> = entries
+        .iter()
+        .enumerate()
+        .map(|(index, cells)| {
+            let mut code = String::new();
+            if style.leading_commas {
+                // The first entry is indented like the ones after a `, `.
+                code.push_str(if index == 0 { "  " } else { ", " });
+            }
+            let cells = cells.iter().zip(&widths).filter(|&(_, &width)| width > 0);
+            for (position, (cell, &width)) in cells.enumerate() {
+                if position > 0 {
+                    code.push(' ');
+                }
+                code.push_str(cell);
+                if style.align {
+                    code.push_str(&" ".repeat(width - cell.chars().count()));
+                }
+            }
+            code.truncate(code.trim_end().len());
+            if !style.leading_commas && index + 1 < entries.len() {
+                code.push(',');
+            }
+            code
+        })
+        .collect();
+
+    let comment_column = codes.iter().map(|code| code.chars().count()).max().unwrap_or(0) + 1;
+    let mut codes = codes.into_iter();
+    lines
+        .into_iter()
+        .map(|line| match line {
+            Line::Comment(text) => text,
+            Line::Entry { comment, .. } => {
+                let mut code = codes.next().unwrap_or_default();
+                if let Some(comment) = comment {
+                    let padding = if style.align {
+                        comment_column - code.chars().count()
+                    } else {
+                        1
+                    };
+                    code.push_str(&" ".repeat(padding));
+                    code.push_str(&comment);
+                }
+                code
+            }
+        })
+        .collect()
+}
+
+/// Joins `tokens` with the spacing the formatter puts between them within a line.
+fn render_tokens(tokens: &[&Token], config: &FormatConfig) -> String {
+    let mut text = String::new();
+    let mut pending_space = false;
+    let mut previous_call_ident = false;
+    for (index, token) in tokens.iter().enumerate() {
+        let call = token.text == "(" && config.remove_call_space && previous_call_ident;
+        if pending_space && !call && !needs_no_space_before(token, config) {
+            text.push(' ');
+        }
+        text.push_str(&casing::token_text(token, config));
+        pending_space = needs_space_after(token, tokens.get(index + 1).copied(), config);
+        previous_call_ident = token.is_identifier_like();
+    }
+    text
+}
@@ -1,7 +1,17 @@
 mod analysis;
+mod casing;
+mod comments;
+mod continuation;
 mod emitter;
 mod engine;
+mod fallback;
 mod lexer;
+mod lists;
+mod origin;
+mod pragma;
+mod ranges;
 mod wrapping;
 
-pub use engine::format_text;
+pub use engine::{format_document, format_text};
+pub use fallback::{FallbackRegion, FormatOutput};
+pub use ranges::{FormatRange, format_ranges};
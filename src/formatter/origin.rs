@@ -0,0 +1,331 @@
+use std::{collections::HashMap, ops::Range, path::Path};
+
+use sv_parser::{Locate, SyntaxTree};
+
+use super::{
+    analysis::{AlignedList, BlockSpan, ByteSpan, CaseLabel, ListEntry},
+    lexer::{Token, TokenKind},
+};
+use crate::parser::INPUT_PATH;
+
+/// Tokens re-anchored onto the original source text.
+///
+/// The syntax tree describes the preprocessed text: macros are expanded, included files are
+/// spliced in and inactive `` `ifdef `` branches are gone. Only tokens that come from the input
+/// itself are kept, and the source text between them (directives, macro usages and inactive
+/// branches) is turned back into tokens so nothing is lost when the output is assembled.
+pub(crate) struct SourceTokens {
+    pub(crate) tokens: Vec<Token>,
+    /// Source ranges that must be copied unchanged, such as inactive conditional branches.
+    pub(crate) verbatim: Vec<Range<usize>>,
+    /// Preprocessed offset, source offset and length of every kept token, in order.
+    offsets: Vec<(usize, usize, usize)>,
+}
+
+pub(crate) fn map_to_source(tree: &SyntaxTree, source: &str, tokens: Vec<Token>) -> SourceTokens {
+    let mut mapped = SourceTokens {
+        tokens: Vec::with_capacity(tokens.len()),
+        verbatim: Vec::new(),
+        offsets: Vec::new(),
+    };
+    let mut cursor = 0;
+    for token in tokens {
+        if token.kind == TokenKind::Newline {
+            continue;
+        }
+        let Some(offset) = source_offset(tree, source, &token, cursor) else {
+            continue;
+        };
+        mapped.scan_gap(source, cursor, offset);
+        mapped.offsets.push((token.offset, offset, token.len));
+        cursor = offset + token.len;
+        mapped.tokens.push(Token { offset, ..token });
+    }
+    mapped.scan_gap(source, cursor, source.len());
+    mapped
+}
+
+/// Returns where `token` sits in the input, or `None` when it was produced by a macro expansion
+/// or an included file.
+fn source_offset(tree: &SyntaxTree, source: &str, token: &Token, cursor: usize) -> Option<usize> {
+    let locate = Locate {
+        offset: token.offset,
+        line: 0,
+        len: token.len,
+    };
+    let (path, offset) = tree.get_origin(&locate)?;
+    if path != Path::new(INPUT_PATH) || offset < cursor {
+        return None;
+    }
+    (source.get(offset..offset + token.len) == Some(token.text.as_str())).then_some(offset)
+}
+
+impl SourceTokens {
+    /// Translates offsets keyed by the preprocessed text to source offsets, dropping entries
+    /// whose token did not survive the mapping.
+    pub(crate) fn remap_spans(&self, spans: HashMap<usize, ByteSpan>) -> HashMap<usize, ByteSpan> {
+        spans
+            .into_iter()
+            .filter_map(|(offset, span)| {
+                Some((
+                    self.exact(offset)?,
+                    ByteSpan {
+                        start: self.start(span.start)?,
+                        end: self.bound(span.end),
+                        block: span.block,
+                    },
+                ))
+            })
+            .collect()
+    }
+
+    pub(crate) fn remap_case_labels(&self, groups: Vec<Vec<CaseLabel>>) -> Vec<Vec<CaseLabel>> {
+        groups
+            .into_iter()
+            .map(|labels| {
+                labels
+                    .into_iter()
+                    .filter_map(|label| {
+                        Some(CaseLabel {
+                            start: self.start(label.start)?,
+                            colon: self.exact(label.colon)?,
+                        })
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// Keeps the blocks whose opening and closing keywords are both written in the input.
+    pub(crate) fn remap_blocks(&self, blocks: Vec<BlockSpan>) -> Vec<BlockSpan> {
+        blocks
+            .into_iter()
+            .filter_map(|block| {
+                Some(BlockSpan {
+                    open: self.exact(block.open)?,
+                    close: self.exact(block.close)?,
+                })
+            })
+            .collect()
+    }
+
+    pub(crate) fn remap_starts(&self, starts: Vec<usize>) -> Vec<usize> {
+        starts.into_iter().filter_map(|start| self.start(start)).collect()
+    }
+
+    /// Keeps the lists whose brackets, entries and columns all start at tokens written in the
+    /// input.
+    pub(crate) fn remap_lists(&self, lists: Vec<AlignedList>) -> Vec<AlignedList> {
+        lists
+            .into_iter()
+            .filter_map(|list| {
+                Some(AlignedList {
+                    kind: list.kind,
+                    open: self.exact(list.open)?,
+                    close: self.exact(list.close)?,
+                    entries: list
+                        .entries
+                        .into_iter()
+                        .map(|entry| self.remap_entry(entry))
+                        .collect::<Option<_>>()?,
+                })
+            })
+            .collect()
+    }
+
+    pub(crate) fn remap_entries(&self, entries: Vec<ListEntry>) -> Vec<ListEntry> {
+        entries
+            .into_iter()
+            .filter_map(|entry| self.remap_entry(entry))
+            .collect()
+    }
+
+    fn remap_entry(&self, entry: ListEntry) -> Option<ListEntry> {
+        let columns = entry
+            .columns
+            .into_iter()
+            .map(|column| match column {
+                Some(offset) => self.exact(offset).map(Some),
+                None => Some(None),
+            })
+            .collect::<Option<_>>()?;
+        Some(ListEntry {
+            start: self.exact(entry.start)?,
+            end: self.bound(entry.end),
+            columns,
+        })
+    }
+
+    fn exact(&self, offset: usize) -> Option<usize> {
+        let index = self.offsets.partition_point(|&(pp, _, _)| pp < offset);
+        match self.offsets.get(index) {
+            Some(&(pp, source, _)) if pp == offset => Some(source),
+            _ => None,
+        }
+    }
+
+    /// Source offset of the first kept token at or after the preprocessed offset `offset`.
+    fn bound(&self, offset: usize) -> usize {
+        let index = self.offsets.partition_point(|&(pp, _, _)| pp < offset);
+        self.offsets.get(index).map_or(usize::MAX, |&(_, source, _)| source)
+    }
+
+    /// Source offset where the construct starting at the preprocessed offset `offset` begins.
+    /// When that token came from a macro expansion, this is the macro usage itself.
+    fn start(&self, offset: usize) -> Option<usize> {
+        if let Some(source) = self.exact(offset) {
+            return Some(source);
+        }
+        let index = self.offsets.partition_point(|&(pp, _, _)| pp < offset);
+        let after = match index.checked_sub(1) {
+            Some(prev) => self.offsets[prev].1 + self.offsets[prev].2,
+            None => 0,
+        };
+        let first = self.tokens.partition_point(|token| token.offset < after);
+        self.tokens[first..]
+            .iter()
+            .find(|token| token.kind != TokenKind::Newline)
+            .map(|token| token.offset)
+    }
+
+    /// Rebuilds tokens for the source text in `start..end`, which no kept token covers.
+    fn scan_gap(&mut self, source: &str, start: usize, end: usize) {
+        let bytes = source.as_bytes();
+        let mut pos = start;
+        while pos < end {
+            match bytes[pos] {
+                b'\n' => {
+                    self.tokens.push(Token::new_spanned("\n", TokenKind::Newline, pos, 1));
+                    pos += 1;
+                }
+                byte if byte.is_ascii_whitespace() => pos += 1,
+                b'`' => pos = self.push_backtick(source, pos, end),
+                _ => pos = self.push_inactive(source, pos, end),
+            }
+        }
+    }
+
+    /// Pushes a compiler directive or macro usage starting at `pos` and returns where it ends.
+    fn push_backtick(&mut self, source: &str, pos: usize, end: usize) -> usize {
+        let name_end = identifier_end(source, pos + 1, end);
+        let name = &source[pos + 1..name_end];
+        let (token_end, kind) = match name {
+            "define" => (define_end(source, pos, end), TokenKind::Directive),
+            "ifdef" | "ifndef" | "elsif" | "undef" => {
+                let arg = skip_spaces(source, name_end, end);
+                (identifier_end(source, arg, end), TokenKind::Directive)
+            }
+            "else"
+            | "endif"
+            | "resetall"
+            | "undefineall"
+            | "celldefine"
+            | "endcelldefine"
+            | "nounconnected_drive"
+            | "end_keywords" => (name_end, TokenKind::Directive),
+            _ if is_directive(name) => (line_end(source, pos, end), TokenKind::Directive),
+            _ => (macro_usage_end(source, name_end, end), TokenKind::Other),
+        };
+        let text = source[pos..token_end].trim_end();
+        self.tokens.push(Token::new_spanned(text, kind, pos, text.len()));
+        token_end
+    }
+
+    /// Pushes source text dropped by the preprocessor (an inactive conditional branch) as a
+    /// verbatim region reaching up to the next conditional directive.
+    fn push_inactive(&mut self, source: &str, pos: usize, end: usize) -> usize {
+        let line_start = source[..pos].rfind('\n').map_or(0, |index| index + 1);
+        let start = if source[line_start..pos].trim().is_empty() {
+            line_start
+        } else {
+            pos
+        };
+        let mut region_end = end;
+        let mut search = pos;
+        while let Some(found) = source[search..end].find('`') {
+            let tick = search + found;
+            let name = &source[tick + 1..identifier_end(source, tick + 1, end)];
+            if matches!(name, "ifdef" | "ifndef" | "elsif" | "else" | "endif") {
+                let tick_line = source[..tick].rfind('\n').map_or(0, |index| index + 1);
+                region_end = if tick_line > start && source[tick_line..tick].trim().is_empty() {
+                    tick_line
+                } else {
+                    tick
+                };
+                break;
+            }
+            search = tick + 1;
+        }
+        let text = &source[start..region_end];
+        self.tokens
+            .push(Token::new_spanned(text, TokenKind::Other, start, text.len()));
+        self.verbatim.push(start..region_end);
+        region_end
+    }
+}
+
+fn is_directive(name: &str) -> bool {
+    matches!(
+        name,
+        "include" | "timescale" | "default_nettype" | "unconnected_drive" | "pragma" | "line" | "begin_keywords"
+    )
+}
+
+fn identifier_end(source: &str, pos: usize, end: usize) -> usize {
+    source[pos..end]
+        .find(|ch: char| !(ch.is_ascii_alphanumeric() || ch == '_' || ch == '$'))
+        .map_or(end, |index| pos + index)
+}
+
+fn skip_spaces(source: &str, pos: usize, end: usize) -> usize {
+    source[pos..end]
+        .find(|ch: char| ch != ' ' && ch != '\t')
+        .map_or(end, |index| pos + index)
+}
+
+fn line_end(source: &str, pos: usize, end: usize) -> usize {
+    source[pos..end].find('\n').map_or(end, |index| pos + index)
+}
+
+/// A `` `define `` runs to the end of its line, including backslash continuations.
+fn define_end(source: &str, pos: usize, end: usize) -> usize {
+    let mut line = line_end(source, pos, end);
+    while line < end && source[pos..line].trim_end_matches('\r').ends_with('\\') {
+        line = line_end(source, line + 1, end);
+    }
+    line
+}
+
+/// A macro usage covers its name and, when present, its parenthesized argument list.
+fn macro_usage_end(source: &str, name_end: usize, end: usize) -> usize {
+    let open = skip_spaces(source, name_end, end);
+    if source.as_bytes().get(open) != Some(&b'(') || open >= end {
+        return name_end;
+    }
+    let mut depth = 0usize;
+    let mut in_string = false;
+    let mut escaped = false;
+    for (index, ch) in source[open..end].char_indices() {
+        if in_string {
+            match ch {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                '"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+        match ch {
+            '"' => in_string = true,
+            '(' => depth += 1,
+            ')' => {
+                depth -= 1;
+                if depth == 0 {
+                    return open + index + 1;
+                }
+            }
+            _ => {}
+        }
+    }
+    end
+}
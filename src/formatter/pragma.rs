@@ -0,0 +1,148 @@
+use std::ops::Range;
+
+use super::{
+    analysis::BlockKeywords,
+    lexer::{Token, TokenKind},
+};
+
+/// A `// sv-fmt: ...` control comment.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Pragma {
+    /// Copy everything up to the next `on` pragma unchanged.
+    Off,
+    On,
+    /// Copy the next statement unchanged.
+    Skip,
+}
+
+/// Recognizes `// sv-fmt: off` and friends, in line or block comment form.
+pub(crate) fn parse_pragma(comment: &str) -> Option<Pragma> {
+    let comment = comment.trim();
+    let body = if let Some(body) = comment.strip_prefix("//") {
+        body
+    } else {
+        comment.strip_prefix("/*")?.strip_suffix("*/")?
+    };
+    let directive = body.trim().strip_prefix("sv-fmt:")?.trim();
+    match directive {
+        "off" => Some(Pragma::Off),
+        "on" => Some(Pragma::On),
+        "skip" => Some(Pragma::Skip),
+        _ => None,
+    }
+}
+
+/// Source ranges protected by pragma comments, sorted and without overlaps.
+///
+/// An `off` region runs from the line holding the `off` comment (or from the comment itself
+/// when code precedes it) to the end of the line holding the matching `on`, or to the end of
+/// the input. A `skip` protects the whole lines of the statement that follows it.
+pub(crate) fn pragma_regions(source: &str, tokens: &[Token], blocks: &BlockKeywords) -> Vec<Range<usize>> {
+    let mut regions: Vec<Range<usize>> = Vec::new();
+    let mut off_start = None;
+    let mut idx = 0;
+    while idx < tokens.len() {
+        let token = &tokens[idx];
+        idx += 1;
+        if token.kind != TokenKind::Comment {
+            continue;
+        }
+        match (parse_pragma(&token.text), off_start) {
+            (Some(Pragma::Off), None) => off_start = Some(region_start(source, token.offset)),
+            (Some(Pragma::On), Some(start)) => {
+                regions.push(start..line_end(source, token.offset + token.len));
+                off_start = None;
+            }
+            (Some(Pragma::Skip), None) => {
+                if let Some((first, last)) = next_statement(tokens, idx, blocks) {
+                    let start = line_start(source, tokens[first].offset);
+                    let end = line_end(source, tokens[last].offset + tokens[last].len);
+                    regions.push(start..end);
+                    while idx < tokens.len() && tokens[idx].offset < end {
+                        idx += 1;
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    if let Some(start) = off_start {
+        regions.push(start..source.len());
+    }
+    regions
+}
+
+/// Index range (inclusive) of the statement starting at or after `idx`. A statement ends at a
+/// `;` outside brackets and blocks, or where the block it opens is closed (continuing into a
+/// following `else`).
+fn next_statement(tokens: &[Token], idx: usize, blocks: &BlockKeywords) -> Option<(usize, usize)> {
+    let first = idx
+        + tokens[idx..]
+            .iter()
+            .position(|token| !matches!(token.kind, TokenKind::Newline | TokenKind::Comment))?;
+    let mut depth = 0usize;
+    let mut brackets = 0usize;
+    let mut last = first;
+    for (index, token) in tokens.iter().enumerate().skip(first) {
+        if matches!(token.kind, TokenKind::Newline | TokenKind::Comment) {
+            continue;
+        }
+        last = index;
+        let ends = match token.text.as_str() {
+            "(" | "[" | "{" => {
+                brackets += 1;
+                false
+            }
+            ")" | "]" | "}" => {
+                brackets = brackets.saturating_sub(1);
+                false
+            }
+            ";" => depth == 0 && brackets == 0,
+            _ if blocks.opens(token.offset) => {
+                depth += 1;
+                false
+            }
+            _ if blocks.closes(token.offset) => {
+                depth = depth.saturating_sub(1);
+                depth == 0
+            }
+            _ => false,
+        };
+        if ends && !next_is_else(tokens, index + 1) {
+            break;
+        }
+    }
+    Some((first, last))
+}
+
+fn next_is_else(tokens: &[Token], idx: usize) -> bool {
+    tokens[idx..]
+        .iter()
+        .find(|token| !matches!(token.kind, TokenKind::Newline | TokenKind::Comment))
+        .is_some_and(|token| token.is_keyword("else"))
+}
+
+/// Start of an `off` region: its whole line, or the comment and the whitespace before it.
+fn region_start(source: &str, offset: usize) -> usize {
+    let start = line_start(source, offset);
+    if source[start..offset].trim().is_empty() {
+        start
+    } else {
+        source[..offset].trim_end().len()
+    }
+}
+
+fn line_start(source: &str, offset: usize) -> usize {
+    source[..offset].rfind('\n').map_or(0, |index| index + 1)
+}
+
+/// Offset just past the newline ending the line that contains `offset - 1`.
+fn line_end(source: &str, offset: usize) -> usize {
+    let offset = offset.min(source.len());
+    if source[..offset].ends_with('\n') {
+        return offset;
+    }
+    source[offset..]
+        .find('\n')
+        .map_or(source.len(), |index| offset + index + 1)
+}
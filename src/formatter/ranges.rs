@@ -0,0 +1,80 @@
+use std::ops::Range;
+
+use anyhow::{Result, bail};
+
+use super::engine::format_source;
+use crate::config::FormatConfig;
+
+/// A region of the input to reformat. Ranges are widened to whole lines.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FormatRange {
+    /// 1-based, inclusive line numbers.
+    Lines { start: usize, end: usize },
+    /// Byte offsets into the input, `end` exclusive.
+    Bytes { start: usize, end: usize },
+}
+
+/// Formats only the lines covered by `ranges` and copies everything else verbatim.
+///
+/// Indentation inside the ranges follows the surrounding blocks. Rules that restructure code
+/// across lines (`wrap_multiline_blocks`, `auto_wrap_long_lines`) are disabled so edits never
+/// leak outside the requested ranges.
+pub fn format_ranges(input: &str, config: &FormatConfig, ranges: &[FormatRange]) -> Result<String> {
+    let line_starts = line_starts(input);
+    let mut selected = Vec::with_capacity(ranges.len());
+    for range in ranges {
+        selected.push(line_span(input, &line_starts, *range)?);
+    }
+    let verbatim = complement(selected, input.len());
+
+    let config = FormatConfig {
+        wrap_multiline_blocks: false,
+        auto_wrap_long_lines: false,
+        ..config.clone()
+    };
+    format_source(input, &config, &verbatim)
+}
+
+fn line_starts(input: &str) -> Vec<usize> {
+    let mut starts = vec![0];
+    starts.extend(input.match_indices('\n').map(|(ix, _)| ix + 1));
+    starts
+}
+
+fn line_span(input: &str, line_starts: &[usize], range: FormatRange) -> Result<Range<usize>> {
+    let (first_line, last_line) = match range {
+        FormatRange::Lines { start, end } => {
+            if start == 0 || start > end {
+                bail!("invalid line range {start}:{end}");
+            }
+            (start - 1, end - 1)
+        }
+        FormatRange::Bytes { start, end } => {
+            if start > end || end > input.len() {
+                bail!("invalid byte range {start}..{end} for input of {} bytes", input.len());
+            }
+            let first = line_starts.partition_point(|&ix| ix <= start) - 1;
+            let last = line_starts.partition_point(|&ix| ix < end.max(start + 1)) - 1;
+            (first, last)
+        }
+    };
+    let line_end = |line: usize| line_starts.get(line + 1).copied().unwrap_or(input.len());
+    let start = line_starts.get(first_line).copied().unwrap_or(input.len());
+    Ok(start..line_end(last_line).max(start))
+}
+
+fn complement(mut selected: Vec<Range<usize>>, len: usize) -> Vec<Range<usize>> {
+    selected.sort_by_key(|range| range.start);
+    let mut verbatim = Vec::new();
+    let mut cursor = 0;
+    for range in selected {
+        if range.start > cursor {
+            verbatim.push(cursor..range.start);
+        }
+        cursor = cursor.max(range.end);
+    }
+    if cursor < len {
+        verbatim.push(cursor..len);
+    }
+    verbatim
+}
@@ -6,8 +6,8 @@ pub(crate) fn wrap_formatted_output(text: String, config: &FormatConfig) -> Stri
     }
     let mut result = String::with_capacity(text.len());
     for line in text.split_inclusive('\n') {
-        let (body, has_newline) = if line.ends_with('\n') {
-            (&line[..line.len() - 1], true)
+        let (body, has_newline) = if let Some(stripped) = line.strip_suffix('\n') {
+            (stripped, true)
         } else {
             (line, false)
         };
@@ -1,19 +1,44 @@
 use std::{
+    collections::HashMap,
     fs,
-    io::{self, Write},
-    path::{Path, PathBuf},
+    io::{self, IsTerminal, Read, Write},
+    num::NonZeroUsize,
+    path::{Component, Path, PathBuf},
+    process::ExitCode,
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicUsize, Ordering},
+    },
+    thread,
 };
 
-use anyhow::{Context, Result, bail};
-use clap::Parser;
-use sv_fmt::{config, formatter::format_text};
-use walkdir::WalkDir;
+use anyhow::{Context, Result, anyhow, bail};
+use clap::{Parser, Subcommand, ValueEnum};
+use ignore::{
+    WalkBuilder,
+    gitignore::{Gitignore, GitignoreBuilder},
+};
+use sv_fmt::{
+    config::{self, ConfigResolver, FormatConfig},
+    diff,
+    formatter::{FallbackRegion, FormatRange, format_document, format_ranges},
+    parser::ParseError,
+};
 
 #[derive(Debug, Parser)]
-#[command(author, version, about = "SystemVerilog formatter")]
+#[command(
+    author,
+    version,
+    about = "SystemVerilog formatter",
+    args_conflicts_with_subcommands = true,
+    subcommand_negates_reqs = true
+)]
 struct Cli {
-    /// Files or directories to format.
-    #[arg(value_name = "FILES", required = true)]
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    /// Files or directories to format. Use `-` to read from stdin and write to stdout.
+    #[arg(value_name = "FILES", required_unless_present = "print_config")]
     paths: Vec<PathBuf>,
 
     /// Overwrite files in place.
@@ -24,51 +49,233 @@ struct Cli {
     #[arg(long = "check", conflicts_with = "in_place")]
     check: bool,
 
+    /// Print a unified diff for files that need formatting (implies --check exit codes).
+    #[arg(long = "diff", conflicts_with = "in_place")]
+    diff: bool,
+
+    /// Number of context lines shown around each diff hunk.
+    #[arg(long = "diff-context", value_name = "N", default_value_t = 3)]
+    diff_context: usize,
+
+    /// When to colorize diff output.
+    #[arg(long = "color", value_name = "WHEN", value_enum, default_value_t = ColorChoice::Auto)]
+    color: ColorChoice,
+
+    /// Only reformat lines START through END (1-based, inclusive). Repeatable.
+    #[arg(long = "lines", value_name = "START:END", value_parser = parse_line_range)]
+    lines: Vec<FormatRange>,
+
+    /// Number of files to format in parallel (defaults to the number of CPUs).
+    #[arg(short = 'j', long = "jobs", value_name = "N")]
+    jobs: Option<NonZeroUsize>,
+
+    /// Path used for config lookup, extension filtering and messages when reading from stdin.
+    #[arg(long = "stdin-filepath", value_name = "PATH")]
+    stdin_filepath: Option<PathBuf>,
+
+    /// Skip paths matching this gitignore-style pattern when walking directories. Repeatable.
+    #[arg(long = "exclude", value_name = "GLOB")]
+    exclude: Vec<String>,
+
+    /// Also skip files ignored by .gitignore when walking directories.
+    #[arg(long = "respect-gitignore")]
+    respect_gitignore: bool,
+
+    /// Define a preprocessor macro as NAME or NAME=VALUE. Repeatable.
+    #[arg(short = 'D', long = "define", value_name = "NAME[=VALUE]")]
+    defines: Vec<String>,
+
+    /// Add a directory to the `include search path. Repeatable.
+    #[arg(short = 'I', long = "include-dir", value_name = "DIR")]
+    include_dirs: Vec<PathBuf>,
+
     /// Path to a sv-fmt.toml configuration file.
     #[arg(long = "config", value_name = "PATH")]
     config: Option<PathBuf>,
+
+    /// Print the configuration that applies to PATH (or the current directory) and exit.
+    #[arg(long = "print-config", value_name = "PATH", conflicts_with = "paths")]
+    print_config: Option<Option<PathBuf>>,
 }
 
-fn main() -> Result<()> {
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Write a commented sv-fmt.toml with every option set to its default.
+    Init {
+        /// Where to write the file.
+        #[arg(value_name = "PATH", default_value = config::CONFIG_FILE_NAME)]
+        path: PathBuf,
+
+        /// Overwrite an existing file.
+        #[arg(long = "force")]
+        force: bool,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum ColorChoice {
+    Auto,
+    Always,
+    Never,
+}
+
+impl ColorChoice {
+    fn enabled(self) -> bool {
+        match self {
+            ColorChoice::Auto => io::stdout().is_terminal(),
+            ColorChoice::Always => true,
+            ColorChoice::Never => false,
+        }
+    }
+}
+
+/// Exit status when files need formatting or exceed `max_line_length`.
+const EXIT_NEEDS_FORMATTING: u8 = 1;
+/// Exit status when a file could not be read, parsed or written, or the run itself failed.
+const EXIT_ERROR: u8 = 2;
+
+fn main() -> ExitCode {
     let cli = Cli::parse();
+    match run(&cli) {
+        Ok(code) => code,
+        Err(err) => {
+            eprintln!("Error: {err:?}");
+            ExitCode::from(EXIT_ERROR)
+        }
+    }
+}
+
+fn run(cli: &Cli) -> Result<ExitCode> {
+    if let Some(Command::Init { path, force }) = &cli.command {
+        write_config_template(path, *force)?;
+        return Ok(ExitCode::SUCCESS);
+    }
+
+    let resolver = ConfigResolver::new(cli.config.as_deref())?;
+    if let Some(target) = &cli.print_config {
+        print_config(&resolver, target.as_deref())?;
+        return Ok(ExitCode::SUCCESS);
+    }
+    let stdin_mode = cli.paths.iter().any(|path| is_stdin_path(path));
+    if stdin_mode {
+        if cli.paths.len() > 1 {
+            bail!("`-` (stdin) cannot be combined with other paths");
+        }
+        if cli.in_place {
+            bail!("--in-place cannot be used when reading from stdin");
+        }
+    } else if cli.stdin_filepath.is_some() {
+        bail!("--stdin-filepath requires `-` as the input path");
+    }
 
-    let config = config::load_config(cli.config.as_deref())?;
-    let files = collect_files(&cli.paths)?;
+    let collected = if stdin_mode {
+        CollectedFiles {
+            files: vec![cli.stdin_filepath.clone().unwrap_or_else(|| PathBuf::from("<stdin>"))],
+            ..CollectedFiles::default()
+        }
+    } else {
+        let filter = FileFilter::new(cli)?;
+        collect_files(&cli.paths, &filter, &resolver)?
+    };
+    let mut summary = Summary {
+        failed: collected.errors.len(),
+        skipped: collected.skipped,
+        ..Summary::default()
+    };
+    for err in &collected.errors {
+        eprintln!("error: {err:#}");
+    }
+    let files = collected.files;
     if files.is_empty() {
+        if summary.failed > 0 {
+            return Ok(ExitCode::from(EXIT_ERROR));
+        }
         bail!("no SystemVerilog files found to format");
     }
 
-    if !cli.check && !cli.in_place && files.len() > 1 {
+    let check_mode = cli.check || cli.diff;
+    let use_color = cli.diff && cli.color.enabled();
+    if !check_mode && !cli.in_place && files.len() > 1 {
         bail!("formatting multiple files requires --in-place or --check");
     }
+    if !cli.lines.is_empty() && files.len() > 1 {
+        bail!("--lines can only be used with a single input file");
+    }
+
+    let jobs = cli
+        .jobs
+        .or_else(|| thread::available_parallelism().ok())
+        .map_or(1, NonZeroUsize::get);
+    let results = run_parallel(&files, jobs, |path| format_file(path, cli, &resolver, stdin_mode));
 
     let mut failed_paths = Vec::new();
     let mut lint_failures: Vec<(PathBuf, Vec<LineLengthViolation>)> = Vec::new();
 
-    for path in files {
-        let original = read_input(&path)?;
-        let formatted = format_text(&original, &config)?;
-        let normalized = ensure_trailing_newline(&formatted);
-        let original_normalized = ensure_trailing_newline(&original);
+    // Failures are reported per file so one bad input never stops the rest of the run.
+    for (path, result) in files.iter().zip(results) {
+        let FormattedFile {
+            path,
+            original,
+            formatted,
+            violations,
+            fallback_regions,
+            skipped,
+        } = match result {
+            Ok(file) => file,
+            Err(err) => {
+                report_file_error(path, &err);
+                summary.failed += 1;
+                continue;
+            }
+        };
+        for region in &fallback_regions {
+            report_parse_error(&path, &region.error);
+            eprintln!(
+                "    = lines {}-{} could not be parsed; only indentation and trailing whitespace were formatted",
+                region.start_line, region.end_line
+            );
+        }
 
-        let violations = line_length_violations(&normalized, config.max_line_length);
         if !violations.is_empty() {
             lint_failures.push((path.clone(), violations));
         }
+        // Code that does not parse must not pass a check, however its whitespace looks.
+        let unparsed = check_mode && !fallback_regions.is_empty();
+        if skipped {
+            summary.skipped += 1;
+        } else if formatted != original {
+            summary.changed += 1;
+        } else if !unparsed {
+            summary.unchanged += 1;
+        }
+        if unparsed {
+            summary.failed += 1;
+        }
 
-        if cli.check {
-            if normalized != original_normalized {
-                failed_paths.push(path.clone());
+        if check_mode {
+            if formatted != original {
+                if cli.diff {
+                    let label = path.display().to_string();
+                    let rendered = diff::unified_diff(&original, &formatted, &label, cli.diff_context);
+                    let rendered = if use_color { diff::colorize(&rendered) } else { rendered };
+                    io::stdout().write_all(rendered.as_bytes())?;
+                }
+                failed_paths.push(path);
             }
             continue;
         }
 
         if cli.in_place {
-            if normalized != original_normalized {
-                fs::write(&path, normalized)?;
+            if formatted != original
+                && let Err(err) =
+                    fs::write(&path, formatted).with_context(|| format!("failed to write {}", path.display()))
+            {
+                report_file_error(&path, &err);
+                summary.changed -= 1;
+                summary.failed += 1;
             }
         } else {
-            io::stdout().write_all(normalized.as_bytes())?;
+            io::stdout().write_all(formatted.as_bytes())?;
         }
     }
 
@@ -84,48 +291,440 @@ fn main() -> Result<()> {
                     "line {} has {} columns (max {}) in {}",
                     violation.line,
                     violation.columns,
-                    config.max_line_length,
+                    violation.limit,
                     path.display()
                 );
                 eprintln!("    | {}", violation.preview);
-                if config.max_line_length > 0 {
-                    eprintln!("{}", caret_marker(&violation.preview, config.max_line_length));
+                if violation.limit > 0 {
+                    eprintln!("{}", caret_marker(&violation.preview, violation.limit));
                 }
             }
         }
         eprintln!("hint: adjust max_line_length in sv-fmt.toml or via --config if needed");
     }
-    if cli.check && (!failed_paths.is_empty() || !lint_failures.is_empty()) {
-        std::process::exit(1);
+    // Output written to stdout is the whole result; the summary is only useful for batch runs.
+    if check_mode || cli.in_place {
+        eprintln!("{}", summary.render(check_mode));
+    }
+
+    if summary.failed > 0 {
+        return Ok(ExitCode::from(EXIT_ERROR));
+    }
+    if check_mode && (!failed_paths.is_empty() || !lint_failures.is_empty()) {
+        return Ok(ExitCode::from(EXIT_NEEDS_FORMATTING));
+    }
+    if !check_mode && !lint_failures.is_empty() {
+        eprintln!("error: line length violations detected; see output above");
+        return Ok(ExitCode::from(EXIT_NEEDS_FORMATTING));
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+/// Per-file outcome counts printed at the end of `--check` and `--in-place` runs.
+#[derive(Debug, Default)]
+struct Summary {
+    changed: usize,
+    unchanged: usize,
+    failed: usize,
+    skipped: usize,
+}
+
+impl Summary {
+    fn render(&self, check_mode: bool) -> String {
+        let changed = if check_mode { "need formatting" } else { "formatted" };
+        format!(
+            "{} {changed}, {} unchanged, {} failed, {} skipped",
+            self.changed, self.unchanged, self.failed, self.skipped
+        )
+    }
+}
+
+fn report_file_error(path: &Path, err: &anyhow::Error) {
+    match err.chain().find_map(|cause| cause.downcast_ref::<ParseError>()) {
+        Some(parse_error) => report_parse_error(path, parse_error),
+        None => eprintln!("error: {err:#}"),
     }
-    if !cli.check && !lint_failures.is_empty() {
-        bail!("line length violations detected; see output above");
+}
+
+/// Prints a parse error as `path:line:column: message` followed by the offending line.
+fn report_parse_error(path: &Path, err: &ParseError) {
+    let Some(location) = &err.location else {
+        eprintln!("{}: {}", path.display(), err.message);
+        return;
+    };
+    let file = location.file.as_deref().unwrap_or(path);
+    eprintln!(
+        "{}:{}:{}: {}",
+        file.display(),
+        location.line,
+        location.column,
+        err.message
+    );
+    let preview = line_preview(&location.line_text, location.column);
+    eprintln!("    | {preview}");
+    eprintln!("{}", caret_marker(&preview, location.column - 1));
+    if location.file.is_some() {
+        eprintln!("    = included from {}", path.display());
     }
+}
 
+/// Prints the effective configuration for `target` as TOML, preceded by the files it came from.
+fn print_config(resolver: &ConfigResolver, target: Option<&Path>) -> Result<()> {
+    let target = target.unwrap_or(Path::new("."));
+    let (dir, config) = if target.is_dir() {
+        (target, resolver.resolve_dir(target)?)
+    } else {
+        let dir = target.parent().unwrap_or(Path::new(""));
+        (dir, resolver.resolve(target)?)
+    };
+    let sources = resolver.chain_for_dir(dir)?;
+    let mut table = toml::Table::try_from(&*config).context("failed to serialize configuration")?;
+    if config.overrides.is_empty() {
+        table.remove("overrides");
+    }
+    let rendered = toml::to_string(&table).context("failed to serialize configuration")?;
+
+    let mut stdout = io::stdout().lock();
+    if sources.is_empty() {
+        writeln!(stdout, "# sources: built-in defaults")?;
+    } else {
+        let sources: Vec<_> = sources.iter().map(|path| path.display().to_string()).collect();
+        writeln!(stdout, "# sources: {}", sources.join(", "))?;
+    }
+    stdout.write_all(rendered.as_bytes())?;
+    Ok(())
+}
+
+fn write_config_template(path: &Path, force: bool) -> Result<()> {
+    if path.exists() && !force {
+        bail!("{} already exists (use --force to overwrite)", path.display());
+    }
+    fs::write(path, config::config_template()).with_context(|| format!("failed to write {}", path.display()))?;
+    eprintln!("wrote {}", path.display());
     Ok(())
 }
 
-fn collect_files(paths: &[PathBuf]) -> Result<Vec<PathBuf>> {
-    let mut files = Vec::new();
+struct FormattedFile {
+    path: PathBuf,
+    original: String,
+    formatted: String,
+    violations: Vec<LineLengthViolation>,
+    /// Regions that failed to parse and only had their whitespace normalized.
+    fallback_regions: Vec<FallbackRegion>,
+    /// The input was passed through without formatting.
+    skipped: bool,
+}
+
+fn format_file(path: &Path, cli: &Cli, resolver: &ConfigResolver, stdin_mode: bool) -> Result<FormattedFile> {
+    let original = if stdin_mode {
+        read_stdin(path)?
+    } else {
+        read_input(path)?
+    };
+    if stdin_mode && cli.stdin_filepath.is_some() && !is_sv_file(path) {
+        // Not a SystemVerilog buffer: hand it back untouched so editors keep their text.
+        return Ok(FormattedFile {
+            path: path.to_path_buf(),
+            formatted: original.clone(),
+            original,
+            violations: Vec::new(),
+            fallback_regions: Vec::new(),
+            skipped: true,
+        });
+    }
+
+    let config = parser_config(&*resolver.resolve(path)?, path, cli);
+    let (formatted, fallback_regions) = if cli.lines.is_empty() {
+        format_document(&original, &config).map(|output| (output.text, output.fallback_regions))
+    } else {
+        format_ranges(&original, &config, &cli.lines).map(|text| (text, Vec::new()))
+    }
+    .with_context(|| format!("failed to format {}", path.display()))?;
+    let formatted = ensure_trailing_newline(&formatted);
+
+    // Range formatting leaves the rest of the file untouched, so only whole-file runs are linted.
+    let violations = if cli.lines.is_empty() {
+        line_length_violations(&formatted, config.max_line_length)
+    } else {
+        Vec::new()
+    };
+
+    Ok(FormattedFile {
+        path: path.to_path_buf(),
+        original: ensure_trailing_newline(&original),
+        formatted,
+        violations,
+        fallback_regions,
+        skipped: false,
+    })
+}
+
+/// Adds the preprocessor settings that do not come from config files: the file's own
+/// directory (searched first for `` `include ``), then `-I` directories and `-D` macros.
+fn parser_config(config: &FormatConfig, path: &Path, cli: &Cli) -> FormatConfig {
+    let mut config = config.clone();
+    let mut include_dirs: Vec<PathBuf> = path
+        .parent()
+        .filter(|dir| !dir.as_os_str().is_empty())
+        .map(Path::to_path_buf)
+        .into_iter()
+        .collect();
+    include_dirs.extend(cli.include_dirs.iter().cloned());
+    include_dirs.append(&mut config.include_dirs);
+    config.include_dirs = include_dirs;
+    config.defines.extend(cli.defines.iter().cloned());
+    config
+}
+
+/// sv-parser recurses deeply on large files, so workers get more stack than the 2 MiB default.
+const WORKER_STACK_SIZE: usize = 32 * 1024 * 1024;
+
+/// Applies `job` to every file on up to `jobs` worker threads, returning results in input order.
+fn run_parallel<T, F>(files: &[PathBuf], jobs: usize, job: F) -> Vec<T>
+where
+    T: Send,
+    F: Fn(&Path) -> T + Sync,
+{
+    // Even a single job runs on a worker, as the main thread's stack is too small for the parser.
+    let jobs = jobs.clamp(1, files.len().max(1));
+    let next = AtomicUsize::new(0);
+    let slots: Mutex<Vec<Option<T>>> = Mutex::new(files.iter().map(|_| None).collect());
+    thread::scope(|scope| {
+        for _ in 0..jobs {
+            thread::Builder::new()
+                .stack_size(WORKER_STACK_SIZE)
+                .spawn_scoped(scope, || {
+                    loop {
+                        let ix = next.fetch_add(1, Ordering::Relaxed);
+                        let Some(path) = files.get(ix) else { break };
+                        let result = job(path);
+                        slots.lock().expect("worker panicked")[ix] = Some(result);
+                    }
+                })
+                .expect("failed to spawn worker thread");
+        }
+    });
+    slots
+        .into_inner()
+        .expect("worker panicked")
+        .into_iter()
+        .map(|slot| slot.expect("every file is processed"))
+        .collect()
+}
+
+fn parse_line_range(value: &str) -> Result<FormatRange, String> {
+    let (start, end) = value
+        .split_once(':')
+        .ok_or_else(|| format!("expected START:END, got `{value}`"))?;
+    let start: usize = start
+        .trim()
+        .parse()
+        .map_err(|_| format!("invalid start line `{start}`"))?;
+    let end: usize = end.trim().parse().map_err(|_| format!("invalid end line `{end}`"))?;
+    if start == 0 || start > end {
+        return Err(format!("line range must satisfy 1 <= START <= END, got `{value}`"));
+    }
+    Ok(FormatRange::Lines { start, end })
+}
+
+/// Name of the per-directory ignore file honored during directory walks.
+const IGNORE_FILE_NAME: &str = ".svfmtignore";
+
+struct FileFilter {
+    cwd: PathBuf,
+    cli_excludes: Option<Gitignore>,
+    respect_gitignore: bool,
+    explicit_config: bool,
+}
+
+impl FileFilter {
+    fn new(cli: &Cli) -> Result<Self> {
+        let cwd = std::env::current_dir().context("failed to resolve the current directory")?;
+        let cli_excludes = if cli.exclude.is_empty() {
+            None
+        } else {
+            Some(build_excludes(&cwd, &cli.exclude, "--exclude")?)
+        };
+        Ok(Self {
+            cwd,
+            cli_excludes,
+            respect_gitignore: cli.respect_gitignore,
+            explicit_config: cli.config.is_some(),
+        })
+    }
+
+    /// Exclude matchers and gitignore handling for a walk rooted at `dir`.
+    fn walk_settings(&self, dir: &Path, resolver: &ConfigResolver) -> Result<WalkExcludes> {
+        let mut fixed: Vec<Gitignore> = self.cli_excludes.iter().cloned().collect();
+        let nested = if self.explicit_config {
+            fixed.extend(config_excludes(&self.cwd, resolver.chain_for_dir(dir)?)?);
+            None
+        } else {
+            let nested = ConfigExcludes::new(self.cwd.clone());
+            nested.for_dir(dir)?;
+            Some(nested)
+        };
+        Ok(WalkExcludes {
+            cwd: self.cwd.clone(),
+            fixed,
+            nested,
+            respect_gitignore: self.respect_gitignore || resolver.resolve_dir(dir)?.respect_gitignore,
+        })
+    }
+}
+
+/// Paths skipped by a directory walk.
+struct WalkExcludes {
+    cwd: PathBuf,
+    fixed: Vec<Gitignore>,
+    /// Excludes of the configs discovered per directory, unless `--config` is given.
+    nested: Option<ConfigExcludes>,
+    respect_gitignore: bool,
+}
+
+impl WalkExcludes {
+    fn is_excluded(&self, path: &Path, is_dir: bool) -> bool {
+        let absolute = absolute_path(&self.cwd, path);
+        let matches = |matcher: &Gitignore| matcher.matched(&absolute, is_dir).is_ignore();
+        if self.fixed.iter().any(matches) {
+            return true;
+        }
+        let (Some(nested), Some(parent)) = (&self.nested, absolute.parent()) else {
+            return false;
+        };
+        nested
+            .for_dir(parent)
+            .is_ok_and(|excludes| excludes.iter().any(matches))
+    }
+}
+
+/// `exclude` matchers of the config chain of each directory, cached per directory.
+struct ConfigExcludes {
+    cwd: PathBuf,
+    by_dir: Arc<Mutex<HashMap<PathBuf, Arc<Vec<Gitignore>>>>>,
+}
+
+impl ConfigExcludes {
+    fn new(cwd: PathBuf) -> Self {
+        Self {
+            cwd,
+            by_dir: Arc::default(),
+        }
+    }
+
+    fn for_dir(&self, dir: &Path) -> Result<Arc<Vec<Gitignore>>> {
+        let dir = absolute_path(&self.cwd, dir);
+        if let Some(excludes) = self.by_dir.lock().expect("exclude cache poisoned").get(&dir) {
+            return Ok(Arc::clone(excludes));
+        }
+        let excludes = Arc::new(config_excludes(&self.cwd, config::config_chain(&dir)?)?);
+        self.by_dir
+            .lock()
+            .expect("exclude cache poisoned")
+            .insert(dir, Arc::clone(&excludes));
+        Ok(excludes)
+    }
+}
+
+/// Matchers for the `exclude` patterns of the config files in `chain`.
+fn config_excludes(cwd: &Path, chain: Vec<PathBuf>) -> Result<Vec<Gitignore>> {
+    let mut excludes = Vec::new();
+    for config_path in chain {
+        let config = config::load_config(Some(&config_path))?;
+        if config.exclude.is_empty() {
+            continue;
+        }
+        let root = config_path
+            .parent()
+            .map_or_else(|| cwd.to_path_buf(), |dir| absolute_path(cwd, dir));
+        let origin = format!("exclude (in {})", config_path.display());
+        excludes.push(build_excludes(&root, &config.exclude, &origin)?);
+    }
+    Ok(excludes)
+}
+
+fn build_excludes(root: &Path, patterns: &[String], origin: &str) -> Result<Gitignore> {
+    let mut builder = GitignoreBuilder::new(root);
+    for pattern in patterns {
+        builder
+            .add_line(None, pattern)
+            .with_context(|| format!("invalid {origin} pattern `{pattern}`"))?;
+    }
+    builder
+        .build()
+        .map_err(|err| anyhow!("invalid {origin} patterns: {err}"))
+}
+
+fn absolute_path(cwd: &Path, path: &Path) -> PathBuf {
+    cwd.join(path)
+        .components()
+        .filter(|component| !matches!(component, Component::CurDir))
+        .collect()
+}
+
+/// Input files found from the command line, plus the paths that were passed over.
+#[derive(Default)]
+struct CollectedFiles {
+    files: Vec<PathBuf>,
+    /// Explicitly named files that are not SystemVerilog sources.
+    skipped: usize,
+    /// Paths that could not be inspected.
+    errors: Vec<anyhow::Error>,
+}
+
+fn collect_files(paths: &[PathBuf], filter: &FileFilter, resolver: &ConfigResolver) -> Result<CollectedFiles> {
+    let mut collected = CollectedFiles::default();
     for path in paths {
-        let metadata = fs::metadata(path).with_context(|| format!("failed to read metadata for {}", path.display()))?;
+        let metadata = match fs::metadata(path) {
+            Ok(metadata) => metadata,
+            Err(err) => {
+                collected
+                    .errors
+                    .push(anyhow!(err).context(format!("failed to read metadata for {}", path.display())));
+                continue;
+            }
+        };
         if metadata.is_dir() {
-            for entry in WalkDir::new(path) {
-                let entry = entry.with_context(|| format!("failed to traverse {}", path.display()))?;
-                if entry.file_type().is_file() && is_sv_file(entry.path()) {
-                    files.push(entry.path().to_path_buf());
+            let excludes = filter.walk_settings(path, resolver)?;
+            let respect_gitignore = excludes.respect_gitignore;
+            let walker = WalkBuilder::new(path)
+                .standard_filters(false)
+                .parents(true)
+                .add_custom_ignore_filename(IGNORE_FILE_NAME)
+                .git_ignore(respect_gitignore)
+                .git_exclude(respect_gitignore)
+                .git_global(respect_gitignore)
+                .require_git(false)
+                .filter_entry(move |entry| {
+                    let is_dir = entry.file_type().is_some_and(|ty| ty.is_dir());
+                    !excludes.is_excluded(entry.path(), is_dir)
+                })
+                .build();
+            for entry in walker {
+                let entry = match entry {
+                    Ok(entry) => entry,
+                    Err(err) => {
+                        collected
+                            .errors
+                            .push(anyhow!(err).context(format!("failed to traverse {}", path.display())));
+                        continue;
+                    }
+                };
+                let is_file = entry.file_type().is_some_and(|ty| ty.is_file());
+                if is_file && is_sv_file(entry.path()) {
+                    collected.files.push(entry.path().to_path_buf());
                 }
             }
-        } else if metadata.is_file() {
-            if is_sv_file(path) {
-                files.push(path.clone());
-            }
+        } else if metadata.is_file() && is_sv_file(path) {
+            collected.files.push(path.clone());
+        } else {
+            collected.skipped += 1;
         }
     }
 
-    files.sort();
-    files.dedup();
-    Ok(files)
+    collected.files.sort();
+    collected.files.dedup();
+    Ok(collected)
 }
 
 fn is_sv_file(path: &Path) -> bool {
@@ -135,8 +734,24 @@ fn is_sv_file(path: &Path) -> bool {
     }
 }
 
+fn is_stdin_path(path: &Path) -> bool {
+    path.as_os_str() == "-"
+}
+
 fn read_input(path: &Path) -> Result<String> {
     let bytes = fs::read(path).with_context(|| format!("failed to read {}", path.display()))?;
+    decode_input(bytes, path)
+}
+
+fn read_stdin(path: &Path) -> Result<String> {
+    let mut bytes = Vec::new();
+    io::stdin()
+        .read_to_end(&mut bytes)
+        .with_context(|| format!("failed to read {} from stdin", path.display()))?;
+    decode_input(bytes, path)
+}
+
+fn decode_input(bytes: Vec<u8>, path: &Path) -> Result<String> {
     let mut text = String::from_utf8(bytes).with_context(|| format!("{} is not valid UTF-8", path.display()))?;
     if text.starts_with('\u{feff}') {
         text.drain(..1);
@@ -172,6 +787,7 @@ fn ensure_trailing_newline(text: &str) -> String {
 struct LineLengthViolation {
     line: usize,
     columns: usize,
+    limit: usize,
     preview: String,
 }
 
@@ -187,6 +803,7 @@ fn line_length_violations(text: &str, max_len: usize) -> Vec<LineLengthViolation
                 Some(LineLengthViolation {
                     line: idx + 1,
                     columns: cols,
+                    limit: max_len,
                     preview: line_preview(line, max_len),
                 })
             } else {
@@ -199,14 +816,12 @@ fn line_length_violations(text: &str, max_len: usize) -> Vec<LineLengthViolation
 fn line_preview(line: &str, max_len: usize) -> String {
     let limit = max_len.saturating_add(20).max(40);
     let mut preview = String::new();
-    let mut count = 0;
-    for ch in line.chars() {
+    for (count, ch) in line.chars().enumerate() {
         if count >= limit {
             preview.push_str("...");
             break;
         }
         preview.push(ch);
-        count += 1;
     }
     preview
 }
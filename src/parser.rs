@@ -1,32 +1,178 @@
-use std::path::Path;
+use std::{
+    fmt, fs,
+    path::{Path, PathBuf},
+};
 
-use anyhow::{Context, Result};
-use sv_parser::{Defines, SyntaxTree, parse_sv_str};
+use anyhow::Result;
+use sv_parser::{Define, DefineText, Defines, Error as SvError, SyntaxTree, parse_sv_str};
+
+/// Path recorded as the origin of the text passed to [`parse`].
+pub const INPUT_PATH: &str = "<memory>";
 
 #[derive(Debug, Clone)]
 pub struct SvParserCfg {
     pub allow_incomplete: bool,
+    /// Predefined macros in `NAME` or `NAME=VALUE` form.
+    pub defines: Vec<String>,
+    /// Directories searched for `` `include `` files, in order.
+    pub include_dirs: Vec<PathBuf>,
 }
 
 impl Default for SvParserCfg {
     fn default() -> Self {
-        Self { allow_incomplete: true }
+        Self {
+            allow_incomplete: true,
+            defines: Vec::new(),
+            include_dirs: Vec::new(),
+        }
     }
 }
 
 pub fn parse(text: &str, cfg: &SvParserCfg) -> Result<SyntaxTree> {
-    let defines: Defines = Defines::default();
-    let include_paths: Vec<&Path> = Vec::new();
+    let defines = build_defines(&cfg.defines);
 
     let (tree, _) = parse_sv_str(
         text,
-        Path::new("<memory>"),
+        Path::new(INPUT_PATH),
         &defines,
-        &include_paths,
+        &cfg.include_dirs,
         false,
         cfg.allow_incomplete,
     )
-    .context("failed to parse SystemVerilog input")?;
+    .map_err(|err| ParseError::new(err, text))?;
 
     Ok(tree)
 }
+
+/// A parse or preprocessor failure, located in the input when sv-parser reports a position.
+#[derive(Debug, Clone)]
+pub struct ParseError {
+    pub kind: ParseErrorKind,
+    pub message: String,
+    pub location: Option<ErrorLocation>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParseErrorKind {
+    /// The text does not match the SystemVerilog grammar.
+    Syntax,
+    /// A malformed compiler directive.
+    Preprocessor,
+    /// An input or `` `include `` file could not be read.
+    File,
+    /// An undefined macro or a macro used with the wrong arguments.
+    Macro,
+    Other,
+}
+
+#[derive(Debug, Clone)]
+pub struct ErrorLocation {
+    /// Included file the error points into, or `None` for the input text itself.
+    pub file: Option<PathBuf>,
+    /// 1-based line number.
+    pub line: usize,
+    /// 1-based column, counted in characters.
+    pub column: usize,
+    /// The offending line without its line terminator.
+    pub line_text: String,
+}
+
+impl ParseError {
+    fn new(err: SvError, input: &str) -> Self {
+        let mut err = err;
+        let mut included = false;
+        while let SvError::Include { source } = err {
+            err = *source;
+            included = true;
+        }
+        let (kind, message, position) = match err {
+            SvError::Parse(position) => (ParseErrorKind::Syntax, "syntax error".to_string(), position),
+            SvError::Preprocess(position) => (
+                ParseErrorKind::Preprocessor,
+                "invalid preprocessor directive".to_string(),
+                position,
+            ),
+            SvError::File { path, source } if included => (
+                ParseErrorKind::File,
+                format!(
+                    "cannot open included file {} ({source}); check include_dirs or -I",
+                    path.display()
+                ),
+                None,
+            ),
+            SvError::File { path, source } => (
+                ParseErrorKind::File,
+                format!("cannot read {}: {source}", path.display()),
+                None,
+            ),
+            SvError::DefineNotFound(name) => (ParseErrorKind::Macro, format!("macro `{name}` is not defined"), None),
+            SvError::DefineNoArgs(name) => (
+                ParseErrorKind::Macro,
+                format!("macro `{name}` requires arguments"),
+                None,
+            ),
+            SvError::DefineArgNotFound(name) => {
+                (ParseErrorKind::Macro, format!("missing macro argument `{name}`"), None)
+            }
+            other => (ParseErrorKind::Other, other.to_string(), None),
+        };
+        let location = position.and_then(|(path, offset)| {
+            if path == Path::new(INPUT_PATH) {
+                Some(ErrorLocation::new(None, input, offset))
+            } else {
+                let text = fs::read_to_string(&path).ok()?;
+                Some(ErrorLocation::new(Some(path), &text, offset))
+            }
+        });
+        Self {
+            kind,
+            message,
+            location,
+        }
+    }
+}
+
+impl ErrorLocation {
+    fn new(file: Option<PathBuf>, text: &str, offset: usize) -> Self {
+        let offset = (0..=offset.min(text.len()))
+            .rev()
+            .find(|&index| text.is_char_boundary(index))
+            .unwrap_or(0);
+        let line_start = text[..offset].rfind('\n').map_or(0, |index| index + 1);
+        let line_end = text[offset..].find('\n').map_or(text.len(), |index| offset + index);
+        Self {
+            file,
+            line: text[..offset].matches('\n').count() + 1,
+            column: text[line_start..offset].chars().count() + 1,
+            line_text: text[line_start..line_end].trim_end_matches('\r').to_string(),
+        }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.location {
+            Some(location) => write!(
+                f,
+                "{} at line {}, column {}",
+                self.message, location.line, location.column
+            ),
+            None => f.write_str(&self.message),
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+fn build_defines(specs: &[String]) -> Defines {
+    let mut defines = Defines::default();
+    for spec in specs {
+        let (name, value) = match spec.split_once('=') {
+            Some((name, value)) => (name.trim(), Some(value)),
+            None => (spec.trim(), None),
+        };
+        let text = value.map(|value| DefineText::new(value.to_string(), None));
+        defines.insert(name.to_string(), Some(Define::new(name.to_string(), Vec::new(), text)));
+    }
+    defines
+}
@@ -116,11 +116,14 @@ endmodule
         .assert()
         .success();
 
+    // Statements directly in a module do not parse. Only their indentation is formatted, and a
+    // check reports the file as failed rather than formatted.
     Command::new(assert_cmd::cargo::cargo_bin!("sv-fmt"))
         .arg("--check")
         .arg(&file)
         .assert()
-        .success();
+        .code(2)
+        .stderr(predicate::str::contains("demo.sv:3:5: syntax error"));
 }
 
 #[test]
@@ -220,7 +223,9 @@ endmodule
 #[test]
 fn check_uses_config_file() {
     let dir = tempdir().unwrap();
-    let file = dir.path().join("demo.sv");
+    fs::create_dir_all(dir.path().join("src")).unwrap();
+    fs::create_dir_all(dir.path().join("conf")).unwrap();
+    let file = dir.path().join("src/demo.sv");
     fs::write(
         &file,
         "module demo;
@@ -232,7 +237,7 @@ endmodule
     )
     .unwrap();
 
-    let config = dir.path().join("sv-fmt.toml");
+    let config = dir.path().join("conf/sv-fmt.toml");
     fs::write(
         &config,
         r#"
@@ -277,3 +282,600 @@ fn reports_line_length_violation() {
         .failure()
         .stderr(predicate::str::contains(expected));
 }
+
+#[test]
+fn diff_prints_unified_diff_and_fails() {
+    let dir = tempdir().unwrap();
+    let file = dir.path().join("demo.sv");
+    fs::write(
+        &file,
+        "module demo;
+assign a = foo ( b );
+endmodule
+",
+    )
+    .unwrap();
+
+    Command::new(assert_cmd::cargo::cargo_bin!("sv-fmt"))
+        .arg("--diff")
+        .arg("--color")
+        .arg("never")
+        .arg(&file)
+        .assert()
+        .failure()
+        .stdout(predicate::str::contains("@@ -1,3 +1,3 @@"))
+        .stdout(predicate::str::contains("-assign a = foo ( b );"))
+        .stdout(predicate::str::contains("+  assign a = foo(b);"))
+        .stderr(predicate::str::contains("needs formatting"));
+
+    let unchanged = fs::read_to_string(&file).unwrap();
+    assert!(
+        unchanged.contains("foo ( b )"),
+        "--diff must not rewrite files: {unchanged}"
+    );
+}
+
+#[test]
+fn diff_is_silent_when_formatted() {
+    let dir = tempdir().unwrap();
+    let file = dir.path().join("demo.sv");
+    fs::write(
+        &file,
+        "module demo;
+  assign a = foo(b);
+endmodule
+",
+    )
+    .unwrap();
+
+    Command::new(assert_cmd::cargo::cargo_bin!("sv-fmt"))
+        .arg("--diff")
+        .arg(&file)
+        .assert()
+        .success()
+        .stdout(predicate::str::is_empty());
+}
+
+#[test]
+fn formats_stdin_to_stdout() {
+    Command::new(assert_cmd::cargo::cargo_bin!("sv-fmt"))
+        .arg("-")
+        .write_stdin("module demo;\nassign a = foo ( b );\nendmodule\n")
+        .assert()
+        .success()
+        .stdout("module demo;\n  assign a = foo(b);\nendmodule\n");
+}
+
+#[test]
+fn stdin_filepath_is_used_for_messages_and_filtering() {
+    Command::new(assert_cmd::cargo::cargo_bin!("sv-fmt"))
+        .arg("--check")
+        .arg("--stdin-filepath")
+        .arg("rtl/top.sv")
+        .arg("-")
+        .write_stdin("module top;\nassign a = b;\nendmodule\n")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("needs formatting: rtl/top.sv"));
+
+    Command::new(assert_cmd::cargo::cargo_bin!("sv-fmt"))
+        .arg("--stdin-filepath")
+        .arg("notes.txt")
+        .arg("-")
+        .write_stdin("module top;\nassign a = b;\n")
+        .assert()
+        .success()
+        .stdout("module top;\nassign a = b;\n");
+}
+
+#[test]
+fn lines_option_limits_formatting() {
+    Command::new(assert_cmd::cargo::cargo_bin!("sv-fmt"))
+        .arg("--lines")
+        .arg("3:3")
+        .arg("-")
+        .write_stdin("module demo;\nassign a = foo ( b );\nassign c = bar ( d );\nendmodule\n")
+        .assert()
+        .success()
+        .stdout("module demo;\nassign a = foo ( b );\n  assign c = bar(d);\nendmodule\n");
+
+    Command::new(assert_cmd::cargo::cargo_bin!("sv-fmt"))
+        .arg("--lines")
+        .arg("3")
+        .arg("-")
+        .write_stdin("module demo;\nendmodule\n")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("expected START:END"));
+}
+
+#[test]
+fn parallel_check_reports_in_sorted_order() {
+    let dir = tempdir().unwrap();
+    let names = ["e.sv", "b.sv", "d.sv", "a.sv", "c.sv"];
+    for name in names {
+        fs::write(dir.path().join(name), "module m;\nassign a = b;\nendmodule\n").unwrap();
+    }
+
+    let output = Command::new(assert_cmd::cargo::cargo_bin!("sv-fmt"))
+        .arg("--check")
+        .arg("--jobs")
+        .arg("3")
+        .arg(dir.path())
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    let reported: Vec<&str> = stderr
+        .lines()
+        .filter_map(|line| line.strip_prefix("needs formatting: "))
+        .collect();
+    let mut expected: Vec<String> = names
+        .iter()
+        .map(|name| dir.path().join(name).display().to_string())
+        .collect();
+    expected.sort();
+    assert_eq!(reported, expected);
+}
+
+fn write_unformatted_tree(root: &Path, files: &[&str]) {
+    for file in files {
+        let path = root.join(file);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, "module m;\nassign a = b;\nendmodule\n").unwrap();
+    }
+}
+
+fn reported_paths(root: &Path, args: &[&str]) -> Vec<String> {
+    let output = Command::new(assert_cmd::cargo::cargo_bin!("sv-fmt"))
+        .current_dir(root)
+        .args(args)
+        .output()
+        .unwrap();
+    String::from_utf8(output.stderr)
+        .unwrap()
+        .lines()
+        .filter_map(|line| line.strip_prefix("needs formatting: "))
+        .map(|path| path.replace('\\', "/"))
+        .collect()
+}
+
+#[test]
+fn exclude_patterns_skip_directories() {
+    let dir = tempdir().unwrap();
+    write_unformatted_tree(dir.path(), &["rtl/top.sv", "rtl/vendor/ip.sv", "gen/regs.sv"]);
+
+    let reported = reported_paths(
+        dir.path(),
+        &["--check", "--exclude", "vendor/", "--exclude", "/gen", "."],
+    );
+    assert_eq!(reported, vec!["./rtl/top.sv"]);
+
+    fs::write(dir.path().join("sv-fmt.toml"), "exclude = [\"rtl/vendor/**\"]\n").unwrap();
+    let reported = reported_paths(dir.path(), &["--check", "."]);
+    assert_eq!(reported, vec!["./gen/regs.sv", "./rtl/top.sv"]);
+}
+
+#[test]
+fn nested_config_excludes_apply_from_any_walk_root() {
+    let dir = tempdir().unwrap();
+    write_unformatted_tree(dir.path(), &["rtl/top.sv", "rtl/sub/core.sv", "rtl/sub/gen/regs.sv"]);
+    fs::write(dir.path().join("rtl/sub/sv-fmt.toml"), "exclude = [\"gen/\"]\n").unwrap();
+
+    let reported = reported_paths(dir.path(), &["--check", "rtl"]);
+    assert_eq!(reported, vec!["rtl/sub/core.sv", "rtl/top.sv"]);
+
+    let reported = reported_paths(dir.path(), &["--check", "rtl/sub"]);
+    assert_eq!(reported, vec!["rtl/sub/core.sv"]);
+}
+
+#[test]
+fn svfmtignore_and_gitignore_are_honored() {
+    let dir = tempdir().unwrap();
+    write_unformatted_tree(dir.path(), &["rtl/top.sv", "rtl/gen_regs.sv", "build/out.sv"]);
+    fs::write(dir.path().join("rtl/.svfmtignore"), "gen_*.sv\n").unwrap();
+    fs::write(dir.path().join(".gitignore"), "build/\n").unwrap();
+
+    let reported = reported_paths(dir.path(), &["--check", "."]);
+    assert_eq!(reported, vec!["./build/out.sv", "./rtl/top.sv"]);
+
+    let reported = reported_paths(dir.path(), &["--check", "--respect-gitignore", "."]);
+    assert_eq!(reported, vec!["./rtl/top.sv"]);
+}
+
+#[test]
+fn discovers_nested_configs_per_file() {
+    let dir = tempdir().unwrap();
+    fs::create_dir_all(dir.path().join(".git")).unwrap();
+    fs::write(
+        dir.path().join("sv-fmt.toml"),
+        "indent_width = 4\nremove_call_space = false\n",
+    )
+    .unwrap();
+    fs::create_dir_all(dir.path().join("tb/legacy")).unwrap();
+    fs::write(dir.path().join("tb/sv-fmt.toml"), "indent_width = 3\n").unwrap();
+    fs::write(dir.path().join("tb/legacy/sv-fmt.toml"), "root = true\n").unwrap();
+
+    let source = "module m;\nassign a = foo (b);\nendmodule\n";
+    for file in ["top.sv", "tb/env.sv", "tb/legacy/old.sv"] {
+        fs::write(dir.path().join(file), source).unwrap();
+    }
+
+    Command::new(assert_cmd::cargo::cargo_bin!("sv-fmt"))
+        .current_dir(dir.path().join("tb"))
+        .arg("-i")
+        .arg(dir.path())
+        .assert()
+        .success();
+
+    let read = |file: &str| fs::read_to_string(dir.path().join(file)).unwrap();
+    assert_eq!(read("top.sv"), "module m;\n    assign a = foo (b);\nendmodule\n");
+    assert_eq!(
+        read("tb/env.sv"),
+        "module m;\n   assign a = foo (b);\nendmodule\n",
+        "nested config should inherit remove_call_space and override indent_width"
+    );
+    assert_eq!(
+        read("tb/legacy/old.sv"),
+        "module m;\n  assign a = foo(b);\nendmodule\n",
+        "root = true should stop inheritance"
+    );
+}
+
+#[test]
+fn overrides_apply_to_matching_files() {
+    let dir = tempdir().unwrap();
+    fs::create_dir_all(dir.path().join(".git")).unwrap();
+    fs::write(
+        dir.path().join("sv-fmt.toml"),
+        r#"
+indent_width = 2
+
+[[overrides]]
+files = ["tb/**/*.sv"]
+indent_width = 4
+
+[[overrides]]
+files = ["*_uvm.sv"]
+remove_call_space = false
+"#,
+    )
+    .unwrap();
+    fs::create_dir_all(dir.path().join("tb/env")).unwrap();
+    fs::create_dir_all(dir.path().join("rtl")).unwrap();
+
+    let source = "module m;\nassign a = foo (b);\nendmodule\n";
+    for file in ["rtl/top.sv", "tb/env/env.sv", "tb/env/agent_uvm.sv"] {
+        fs::write(dir.path().join(file), source).unwrap();
+    }
+
+    Command::new(assert_cmd::cargo::cargo_bin!("sv-fmt"))
+        .arg("-i")
+        .arg(dir.path())
+        .assert()
+        .success();
+
+    let read = |file: &str| fs::read_to_string(dir.path().join(file)).unwrap();
+    assert_eq!(read("rtl/top.sv"), "module m;\n  assign a = foo(b);\nendmodule\n");
+    assert_eq!(read("tb/env/env.sv"), "module m;\n    assign a = foo(b);\nendmodule\n");
+    assert_eq!(
+        read("tb/env/agent_uvm.sv"),
+        "module m;\n    assign a = foo (b);\nendmodule\n"
+    );
+}
+
+#[test]
+fn rejects_unknown_config_keys() {
+    let dir = tempdir().unwrap();
+    let file = dir.path().join("demo.sv");
+    fs::write(&file, "module demo;\nendmodule\n").unwrap();
+    let config = dir.path().join("sv-fmt.toml");
+    fs::write(
+        &config,
+        "indent_width = 2\nindent_widht = 4\n\n[[overrides]]\nfiles = [\"*.sv\"]\n  use_tab = true\n",
+    )
+    .unwrap();
+
+    Command::new(assert_cmd::cargo::cargo_bin!("sv-fmt"))
+        .arg("--check")
+        .arg("--config")
+        .arg(&config)
+        .arg(&file)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(format!(
+            "unknown key `indent_widht` at {}:2:1 (did you mean `indent_width`?)",
+            config.display()
+        )))
+        .stderr(predicate::str::contains(format!(
+            "unknown key `use_tab` in [[overrides]] at {}:6:3 (did you mean `use_tabs`?)",
+            config.display()
+        )));
+
+    fs::write(&config, "allow_unknown_keys = true\nindent_widht = 4\n").unwrap();
+    Command::new(assert_cmd::cargo::cargo_bin!("sv-fmt"))
+        .arg("--check")
+        .arg("--config")
+        .arg(&config)
+        .arg(&file)
+        .assert()
+        .success();
+
+    fs::write(
+        &config,
+        "[[overrides]]\nfiles = [\"*.sv\"]\n[[overrides.overrides]]\nfiles = [\"demo.sv\"]\n",
+    )
+    .unwrap();
+    Command::new(assert_cmd::cargo::cargo_bin!("sv-fmt"))
+        .arg("--check")
+        .arg("--config")
+        .arg(&config)
+        .arg(&file)
+        .assert()
+        .code(2)
+        .stderr(predicate::str::contains("[[overrides]] entries in"))
+        .stderr(predicate::str::contains("cannot be nested"))
+        .stderr(predicate::str::contains("did you mean").not());
+
+    // Keys of the whole run are rejected in an override even when unknown keys are allowed.
+    fs::write(
+        &config,
+        "allow_unknown_keys = true\n[[overrides]]\nfiles = [\"*.sv\"]\nexclude = [\"gen/\"]\n",
+    )
+    .unwrap();
+    Command::new(assert_cmd::cargo::cargo_bin!("sv-fmt"))
+        .arg("--check")
+        .arg("--config")
+        .arg(&config)
+        .arg(&file)
+        .assert()
+        .code(2)
+        .stderr(predicate::str::contains("`exclude` in [[overrides]] in"))
+        .stderr(predicate::str::contains(
+            "applies to the whole run and cannot be set per file",
+        ));
+}
+
+#[test]
+fn print_config_reports_effective_values() {
+    let dir = tempdir().unwrap();
+    fs::create_dir_all(dir.path().join(".git")).unwrap();
+    fs::create_dir_all(dir.path().join("tb")).unwrap();
+    fs::write(
+        dir.path().join("sv-fmt.toml"),
+        "indent_width = 3\n\n[[overrides]]\nfiles = [\"tb/*.sv\"]\nuse_tabs = true\n",
+    )
+    .unwrap();
+
+    Command::new(assert_cmd::cargo::cargo_bin!("sv-fmt"))
+        .current_dir(dir.path())
+        .arg("--print-config")
+        .arg("tb/top.sv")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("# sources: "))
+        .stdout(predicate::str::contains("indent_width = 3\n"))
+        .stdout(predicate::str::contains("use_tabs = true\n"))
+        .stdout(predicate::str::contains("overrides").not());
+
+    Command::new(assert_cmd::cargo::cargo_bin!("sv-fmt"))
+        .current_dir(dir.path())
+        .arg("--print-config")
+        .arg("top.sv")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("use_tabs = false\n"));
+}
+
+#[test]
+fn init_writes_example_config() {
+    let dir = tempdir().unwrap();
+    Command::new(assert_cmd::cargo::cargo_bin!("sv-fmt"))
+        .current_dir(dir.path())
+        .arg("init")
+        .assert()
+        .success();
+    let written = fs::read_to_string(dir.path().join("sv-fmt.toml")).unwrap();
+    assert_eq!(written, fs::read_to_string("sv-fmt.example.toml").unwrap());
+
+    // Every key must be documented and present in the template.
+    let parsed: toml::Table = written.parse().unwrap();
+    let mut defaults = toml::Table::try_from(sv_fmt::config::FormatConfig::default()).unwrap();
+    defaults.remove("overrides");
+    assert_eq!(parsed, defaults);
+
+    Command::new(assert_cmd::cargo::cargo_bin!("sv-fmt"))
+        .current_dir(dir.path())
+        .arg("init")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("already exists"));
+}
+
+#[test]
+fn config_template_documents_every_key() {
+    let defaults = toml::Table::try_from(sv_fmt::config::FormatConfig::default()).unwrap();
+    for key in defaults.keys().filter(|key| *key != "overrides") {
+        assert!(
+            sv_fmt::config::key_doc(key).is_some(),
+            "`{key}` has no entry in KEY_DOCS"
+        );
+    }
+
+    let template = sv_fmt::config::config_template();
+    let lines: Vec<&str> = template.lines().collect();
+    for key in defaults.keys().filter(|key| *key != "overrides") {
+        let index = lines
+            .iter()
+            .position(|line| line.starts_with(&format!("{key} = ")))
+            .unwrap_or_else(|| panic!("`{key}` is missing from the template"));
+        assert!(lines[index - 1].starts_with("# "), "`{key}` has no description");
+    }
+}
+
+#[test]
+fn resolves_includes_and_defines() {
+    let dir = tempdir().unwrap();
+    fs::create_dir_all(dir.path().join(".git")).unwrap();
+    fs::create_dir_all(dir.path().join("rtl")).unwrap();
+    fs::create_dir_all(dir.path().join("inc")).unwrap();
+    fs::write(dir.path().join("inc/defs.svh"), "`define WIDTH 8\n").unwrap();
+    fs::write(dir.path().join("rtl/local.svh"), "`define DEPTH 4\n").unwrap();
+    let source = "`include \"defs.svh\"\n`include \"local.svh\"\nmodule m;\nlogic [`WIDTH-1:0] a [`DEPTH];\nassign a[0] = `INIT;\nendmodule\n";
+    let file = dir.path().join("rtl/top.sv");
+    fs::write(&file, source).unwrap();
+
+    // The header directory is unknown and `INIT is undefined, so only indentation is formatted.
+    Command::new(assert_cmd::cargo::cargo_bin!("sv-fmt"))
+        .arg(&file)
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("cannot open included file"))
+        .stderr(predicate::str::contains("lines 1-6 could not be parsed"))
+        .stdout("`include \"defs.svh\"\n`include \"local.svh\"\nmodule m;\n  logic [`WIDTH-1:0] a [`DEPTH];\n  assign a[0] = `INIT;\nendmodule\n");
+    Command::new(assert_cmd::cargo::cargo_bin!("sv-fmt"))
+        .arg("-I")
+        .arg(dir.path().join("inc"))
+        .arg(&file)
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("macro `INIT` is not defined"));
+
+    let expected = "`include \"defs.svh\"\n`include \"local.svh\"\nmodule m;\n  logic [`WIDTH - 1 : 0] a [`DEPTH];\n  assign a [0] = `INIT;\nendmodule\n";
+    Command::new(assert_cmd::cargo::cargo_bin!("sv-fmt"))
+        .arg("-I")
+        .arg(dir.path().join("inc"))
+        .arg("-D")
+        .arg("INIT=0")
+        .arg(&file)
+        .assert()
+        .success()
+        .stdout(expected);
+
+    fs::write(
+        dir.path().join("sv-fmt.toml"),
+        "include_dirs = [\"inc\"]\ndefines = [\"INIT=0\"]\n",
+    )
+    .unwrap();
+    Command::new(assert_cmd::cargo::cargo_bin!("sv-fmt"))
+        .arg(&file)
+        .assert()
+        .success()
+        .stdout(expected);
+}
+
+#[test]
+fn reports_parse_errors_and_formats_other_files() {
+    let dir = tempdir().unwrap();
+    let bad = dir.path().join("bad.sv");
+    let good = dir.path().join("good.sv");
+    fs::write(&bad, "module m;\nassign a = ;\nendmodule\n").unwrap();
+    fs::write(&good, "module g;\nassign a = b;\nendmodule\n").unwrap();
+    fs::write(dir.path().join("sv-fmt.toml"), "fallback_on_parse_error = false\n").unwrap();
+
+    Command::new(assert_cmd::cargo::cargo_bin!("sv-fmt"))
+        .arg("-i")
+        .arg(&bad)
+        .arg(&good)
+        .assert()
+        .code(2)
+        .stderr(predicate::str::contains("bad.sv:2:12: syntax error"))
+        .stderr(predicate::str::contains(
+            "    | assign a = ;\n    |            ^ column 12",
+        ))
+        .stderr(predicate::str::contains(
+            "1 formatted, 0 unchanged, 1 failed, 0 skipped",
+        ));
+
+    assert_eq!(
+        fs::read_to_string(&good).unwrap(),
+        "module g;\n  assign a = b;\nendmodule\n"
+    );
+    assert_eq!(
+        fs::read_to_string(&bad).unwrap(),
+        "module m;\nassign a = ;\nendmodule\n"
+    );
+}
+
+#[test]
+fn check_summary_and_exit_codes() {
+    let dir = tempdir().unwrap();
+    fs::write(dir.path().join("clean.sv"), "module c;\n  assign a = b;\nendmodule\n").unwrap();
+    fs::write(dir.path().join("messy.sv"), "module m;\nassign a = b;\nendmodule\n").unwrap();
+    fs::write(dir.path().join("notes.txt"), "not verilog\n").unwrap();
+
+    Command::new(assert_cmd::cargo::cargo_bin!("sv-fmt"))
+        .arg("--check")
+        .arg(dir.path().join("clean.sv"))
+        .arg(dir.path().join("messy.sv"))
+        .arg(dir.path().join("notes.txt"))
+        .assert()
+        .code(1)
+        .stderr(predicate::str::contains(
+            "1 need formatting, 1 unchanged, 0 failed, 1 skipped",
+        ));
+
+    // Unreadable inputs are errors, reported with a different exit code than formatting issues.
+    fs::write(dir.path().join("latin1.sv"), b"// caf\xe9\nmodule l;\nendmodule\n").unwrap();
+    Command::new(assert_cmd::cargo::cargo_bin!("sv-fmt"))
+        .arg("--check")
+        .arg(dir.path())
+        .arg(dir.path().join("missing.sv"))
+        .assert()
+        .code(2)
+        .stderr(predicate::str::contains("missing.sv"))
+        .stderr(predicate::str::contains("latin1.sv is not valid UTF-8"))
+        .stderr(predicate::str::contains("needs formatting: "))
+        .stderr(predicate::str::contains(
+            "1 need formatting, 1 unchanged, 2 failed, 0 skipped",
+        ));
+}
+
+#[test]
+fn reports_regions_formatted_in_fallback_mode() {
+    let dir = tempdir().unwrap();
+    let file = dir.path().join("vendor.sv");
+    fs::write(&file, "module m;\nassign a = ;   \nendmodule\n").unwrap();
+
+    Command::new(assert_cmd::cargo::cargo_bin!("sv-fmt"))
+        .arg("-i")
+        .arg(&file)
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("vendor.sv:2:12: syntax error"))
+        .stderr(predicate::str::contains(
+            "lines 1-3 could not be parsed; only indentation and trailing whitespace were formatted",
+        ))
+        .stderr(predicate::str::contains(
+            "1 formatted, 0 unchanged, 0 failed, 0 skipped",
+        ));
+
+    assert_eq!(
+        fs::read_to_string(&file).unwrap(),
+        "module m;\n  assign a = ;\nendmodule\n"
+    );
+
+    // Already formatted, but still a syntax error that a check must not let through.
+    Command::new(assert_cmd::cargo::cargo_bin!("sv-fmt"))
+        .arg("--check")
+        .arg(&file)
+        .assert()
+        .code(2)
+        .stderr(predicate::str::contains("vendor.sv:2:14: syntax error"))
+        .stderr(predicate::str::contains(
+            "0 need formatting, 0 unchanged, 1 failed, 0 skipped",
+        ));
+
+    fs::write(&file, "module m;\nassign a = ;   \nendmodule\n").unwrap();
+    Command::new(assert_cmd::cargo::cargo_bin!("sv-fmt"))
+        .arg("--diff")
+        .arg(&file)
+        .assert()
+        .code(2)
+        .stdout(predicate::str::contains("-assign a = ;   \n+  assign a = ;\n"))
+        .stderr(predicate::str::contains("needs formatting: "))
+        .stderr(predicate::str::contains(
+            "1 need formatting, 0 unchanged, 1 failed, 0 skipped",
+        ));
+}
@@ -1,5 +1,5 @@
-use sv_fmt::config::FormatConfig;
-use sv_fmt::formatter::format_text;
+use sv_fmt::config::{FormatConfig, KeywordCase, LetterCase};
+use sv_fmt::formatter::{FormatRange, format_document, format_ranges, format_text};
 
 fn cfg() -> FormatConfig {
     FormatConfig::default()
@@ -20,7 +20,7 @@ module top;
   initial begin
     if (a) b <= c;
     else
-    c <= d;
+      c <= d;
   end
 endmodule
 ";
@@ -46,6 +46,39 @@ endmodule
     }
 }
 
+#[test]
+fn does_not_drop_code_after_a_syntax_error() {
+    // The parser used to stop at `foo ( bar );` and keep nothing of the unit.
+    let input = "module x;
+`ifdef FOO
+assign a = b,c,d;
+`else
+foo ( bar );
+`endif
+endmodule
+";
+    let output = format_document(input, &cfg()).unwrap();
+    assert_eq!(
+        output.text,
+        "module x;\n`ifdef FOO\n  assign a = b,c,d;\n`else\n  foo ( bar );\n`endif\nendmodule\n"
+    );
+    assert_eq!(output.fallback_regions.len(), 1);
+    assert_eq!(
+        output.fallback_regions[0]
+            .error
+            .location
+            .as_ref()
+            .map(|location| location.line),
+        Some(5)
+    );
+
+    let strict = FormatConfig {
+        fallback_on_parse_error: false,
+        ..cfg()
+    };
+    assert!(format_text(input, &strict).is_err());
+}
+
 #[test]
 fn call_and_comma_spacing() {
     let input = "module x;
@@ -58,6 +91,120 @@ endmodule
     assert!(formatted.contains("foo(a, b, c);"));
 }
 
+#[test]
+fn spaces_binary_operators_and_keeps_unary_tight() {
+    let input = "module x;
+initial begin
+a<=b==c&&!d;
+x+=- y;
+i ++;
+z = pkg :: C|~w;
+end
+endmodule
+";
+    let formatted = format_text(input, &cfg()).unwrap();
+    assert!(
+        formatted.contains("    a <= b == c && !d;\n    x += -y;\n    i++;\n    z = pkg::C | ~w;\n"),
+        "{formatted}"
+    );
+
+    let config = FormatConfig {
+        space_around_operators: false,
+        ..cfg()
+    };
+    let formatted = format_text(input, &config).unwrap();
+    assert!(formatted.contains("    x += - y;\n    i ++;\n"), "{formatted}");
+
+    // A cycle delay is a prefix of the delay, not a binary operator.
+    let input = "module x;
+sequence s;
+a ## 1 b ##[1:3] c;
+endsequence
+property p;
+@(posedge clk) req |-> ##2 ack;
+endproperty
+endmodule
+";
+    let formatted = format_text(input, &cfg()).unwrap();
+    assert!(formatted.contains("    a ##1 b ##[1 : 3] c;\n"), "{formatted}");
+    assert!(
+        formatted.contains("    @(posedge clk) req |-> ##2 ack;\n"),
+        "{formatted}"
+    );
+
+    let input = "module x;
+for (genvar i = 0; i < 4; i ++) begin : g
+end
+for (genvar j = 4; j > 0; -- j) begin : h
+end
+endmodule
+";
+    let formatted = format_text(input, &cfg()).unwrap();
+    assert!(formatted.contains("(genvar i = 0; i < 4; i++)"), "{formatted}");
+    assert!(formatted.contains("(genvar j = 4; j > 0; --j)"), "{formatted}");
+}
+
+#[test]
+fn normalizes_keyword_and_number_case() {
+    let config = FormatConfig {
+        keyword_case: KeywordCase::Lower,
+        number_base_case: LetterCase::Lower,
+        number_digit_case: LetterCase::Lower,
+        ..cfg()
+    };
+    let input = "module x;
+assign End_q = 8'HFF | 'X | 4'SBzZ01;
+endmodule
+";
+    let formatted = format_text(input, &config).unwrap();
+    assert!(
+        formatted.contains("assign End_q = 8'hff | 'x | 4'sbzz01;"),
+        "{formatted}"
+    );
+    assert!(format_text(input, &cfg()).unwrap().contains("8'HFF | 'X | 4'SBzZ01;"));
+
+    // Uppercase keywords only parse once lowercased, which is done when the result parses.
+    let input = "MODULE top;
+INITIAL BEGIN
+a = 8'HFF; // BEGIN
+$display(\"END\");
+END
+ENDMODULE
+";
+    let output = format_document(input, &config).unwrap();
+    assert!(output.fallback_regions.is_empty());
+    assert_eq!(
+        output.text,
+        "module top;
+  initial begin
+    a = 8'hff; // BEGIN
+    $display(\"END\");
+  end
+endmodule
+"
+    );
+    assert_eq!(format_text(input, &cfg()).unwrap().lines().next(), Some("MODULE top;"));
+
+    // Code that still fails to parse may use keywords as identifiers, so its case is kept.
+    let input = "MODULE x;
+INITIAL BEGIN
+Input = 8'HFF; // BEGIN
+END
+ENDMODULE
+";
+    let output = format_document(input, &config).unwrap();
+    assert_eq!(output.fallback_regions.len(), 1);
+    assert_eq!(
+        output.text,
+        "MODULE x;
+  INITIAL BEGIN
+    Input = 8'HFF; // BEGIN
+  END
+ENDMODULE
+"
+    );
+}
+
 #[test]
 fn inline_end_else_one_line() {
     let input = "module x;
@@ -194,7 +341,7 @@ package demo;
 endpackage
 
 interface baz();
-  endinterface
+endinterface
 ";
     assert_eq!(formatted, expected);
 }
@@ -205,9 +352,11 @@ fn auto_wraps_long_lines_when_enabled() {
 assign data = {foo, bar, baz, quux};
 endmodule
 ";
-    let mut cfg = FormatConfig::default();
-    cfg.auto_wrap_long_lines = true;
-    cfg.max_line_length = 20;
+    let cfg = FormatConfig {
+        auto_wrap_long_lines: true,
+        max_line_length: 20,
+        ..FormatConfig::default()
+    };
     let formatted = format_text(input, &cfg).unwrap();
     let mut lines = formatted.lines();
     let assign_line = lines.find(|line| line.contains("assign data")).unwrap();
@@ -227,8 +376,10 @@ endmodule
 
 #[test]
 fn keeps_body_when_wrap_disabled() {
-    let mut cfg = FormatConfig::default();
-    cfg.wrap_multiline_blocks = false;
+    let cfg = FormatConfig {
+        wrap_multiline_blocks: false,
+        ..FormatConfig::default()
+    };
     let input = "module x;
 initial begin
 if (cond)
@@ -243,3 +394,803 @@ endmodule
         "unexpected begin insertion:\n{formatted}"
     );
 }
+
+#[test]
+fn formats_only_requested_lines() {
+    let input = "module top;
+assign a = foo ( b );
+assign   c=d;
+initial begin
+if (x) begin
+foo( a,b );
+end
+end
+endmodule
+";
+    let formatted = format_ranges(input, &cfg(), &[FormatRange::Lines { start: 6, end: 6 }]).unwrap();
+    let expected = "\
+module top;
+assign a = foo ( b );
+assign   c=d;
+initial begin
+if (x) begin
+      foo(a, b);
+end
+end
+endmodule
+";
+    assert_eq!(formatted, expected);
+}
+
+#[test]
+fn byte_ranges_expand_to_whole_lines() {
+    let input = "module top;
+assign a = foo ( b );
+assign   c=d;
+endmodule
+";
+    let offset = input.find("foo").unwrap();
+    let formatted = format_ranges(
+        input,
+        &cfg(),
+        &[FormatRange::Bytes {
+            start: offset,
+            end: offset + 3,
+        }],
+    )
+    .unwrap();
+    assert!(formatted.contains("\n  assign a = foo(b);\n"), "{formatted}");
+    assert!(formatted.contains("\nassign   c=d;\n"), "{formatted}");
+}
+
+#[test]
+fn keeps_directives_macros_and_inactive_branches() {
+    let input = "`define WIDTH 8
+module x(
+input logic [`WIDTH-1:0] a,
+`ifdef USE_B
+  input logic    b,
+`endif
+output logic y
+);
+`ifdef USE_B
+assign y=a&b;
+`else
+assign y=a;
+`endif
+endmodule
+";
+    let expected = "\
+`define WIDTH 8
+module x(
+  input logic [`WIDTH - 1 : 0] a,
+`ifdef USE_B
+  input logic b,
+`endif
+  output logic y
+);
+`ifdef USE_B
+  assign y = a & b;
+`else
+assign y=a;
+`endif
+endmodule
+";
+    let config = FormatConfig {
+        defines: vec!["USE_B".to_string()],
+        ..cfg()
+    };
+    let formatted = format_text(input, &config).unwrap();
+    assert_eq!(formatted, expected);
+}
+
+#[test]
+fn keeps_the_blank_line_ending_a_continued_define() {
+    let input = "`define disp(clk, msg)\\
+  always @(posedge clk)\\
+    $display msg;\\
+
+module M ();
+`disp(clk, (\"x\"))
+endmodule
+";
+    let expected = "`define disp(clk, msg)\\
+  always @(posedge clk)\\
+    $display msg;\\
+
+module M();
+  `disp(clk, (\"x\"))
+endmodule
+";
+    assert_eq!(format_text(input, &cfg()).unwrap(), expected);
+    assert_eq!(format_text(expected, &cfg()).unwrap(), expected);
+}
+
+#[test]
+fn falls_back_to_whitespace_only_for_unparsable_units() {
+    let input = "module good;
+assign a=b;
+endmodule
+
+module bad(input clk);
+always @(posedge clk) begin   
+if (x) begin
+y <= $vendor(@@);
+      end
+end
+endmodule
+";
+    let expected = "\
+module good;
+  assign a = b;
+endmodule
+
+module bad(input clk);
+  always @(posedge clk) begin
+    if (x) begin
+      y <= $vendor(@@);
+    end
+  end
+endmodule
+";
+    let output = format_document(input, &cfg()).unwrap();
+    assert_eq!(output.text, expected);
+    assert_eq!(output.fallback_regions.len(), 1);
+    let region = &output.fallback_regions[0];
+    assert_eq!((region.start_line, region.end_line), (5, 11));
+    assert_eq!(region.error.location.as_ref().map(|location| location.line), Some(8));
+
+    let strict = FormatConfig {
+        fallback_on_parse_error: false,
+        ..cfg()
+    };
+    assert!(format_text(input, &strict).is_err());
+}
+
+#[test]
+fn keeps_regions_protected_by_pragmas() {
+    let input = "module m;
+assign a=b;
+// sv-fmt: off
+localparam int TABLE [4] = '{  1,   2,
+                              30,  40 };
+// sv-fmt: on
+assign c=d;
+always_comb begin
+// sv-fmt: skip
+if (a)   z =  1;
+  else   z = 0;
+w=1;
+end
+assign e=f; /* sv-fmt: off */ assign  g  =  h;
+endmodule
+";
+    let expected = "\
+module m;
+  assign a = b;
+// sv-fmt: off
+localparam int TABLE [4] = '{  1,   2,
+                              30,  40 };
+// sv-fmt: on
+  assign c = d;
+  always_comb begin
+    // sv-fmt: skip
+if (a)   z =  1;
+  else   z = 0;
+    w = 1;
+  end
+  assign e = f; /* sv-fmt: off */ assign  g  =  h;
+endmodule
+";
+    let formatted = format_text(input, &cfg()).unwrap();
+    assert_eq!(formatted, expected);
+}
+
+#[test]
+fn keeps_skipped_statements_in_unparsable_units() {
+    let input = "module bad;
+always @(posedge clk) begin
+// sv-fmt: skip
+x   =   $vendor(@@);
+y<=1;
+  // sv-fmt: skip
+  if (a) begin
+        z = 1;
+     end
+w<=2;
+end
+endmodule
+";
+    let expected = "\
+module bad;
+  always @(posedge clk) begin
+    // sv-fmt: skip
+x   =   $vendor(@@);
+    y<=1;
+    // sv-fmt: skip
+  if (a) begin
+        z = 1;
+     end
+    w<=2;
+  end
+endmodule
+";
+    let output = format_document(input, &cfg()).unwrap();
+    assert_eq!(output.fallback_regions.len(), 1);
+    assert_eq!(output.text, expected);
+}
+
+#[test]
+fn indents_every_block_construct() {
+    let input = "program automatic prg;
+initial begin
+fork
+a = 1;
+join_none
+end
+endprogram
+checker chk(logic clk);
+property p_req;
+@(posedge clk) req |-> ack;
+endproperty
+sequence s_ack;
+ack;
+endsequence
+endchecker
+module m(input clk);
+default clocking cb @(posedge clk);
+input a;
+endclocking
+specify
+(a => b) = 1;
+endspecify
+endmodule
+primitive mux(output o, input a, b, s);
+table
+0 ? 0 : 0;
+endtable
+endprimitive
+config cfg;
+design work.m;
+endconfig
+package p;
+class c;
+extern function void f();
+function new();
+x = 0;
+endfunction
+endclass
+endpackage
+";
+    let expected = "\
+program automatic prg;
+  initial begin
+    fork
+      a = 1;
+    join_none
+  end
+endprogram
+checker chk(logic clk);
+  property p_req;
+    @(posedge clk) req |-> ack;
+  endproperty
+  sequence s_ack;
+    ack;
+  endsequence
+endchecker
+module m(input clk);
+  default clocking cb @(posedge clk);
+    input a;
+  endclocking
+  specify
+    (a => b) = 1;
+  endspecify
+endmodule
+primitive mux(output o, input a, b, s);
+  table
+    0 ? 0 : 0;
+  endtable
+endprimitive
+config cfg;
+  design work.m;
+endconfig
+
+package p;
+
+  class c;
+    extern function void f();
+    function new();
+      x = 0;
+    endfunction
+  endclass
+endpackage
+";
+    let formatted = format_text(input, &cfg()).unwrap();
+    assert_eq!(formatted, expected);
+}
+
+#[test]
+fn indents_single_statement_bodies_on_their_own_line() {
+    let input = "module m;
+always_ff @(posedge clk)
+q <= d;
+always
+@(posedge clk)
+// sample
+r <= d;
+initial
+#10 x = 1;
+always_comb begin
+if (x)
+y = 1;
+else if (z)
+y = 2;
+else
+y = 3;
+for (int i = 0; i < 4; i++)
+if (m[i])
+n = i;
+w = 0;
+end
+endmodule
+";
+    let expected = "\
+module m;
+  always_ff @(posedge clk)
+    q <= d;
+  always
+    @(posedge clk)
+      // sample
+      r <= d;
+  initial
+    # 10 x = 1;
+  always_comb begin
+    if (x)
+      y = 1;
+    else if (z)
+      y = 2;
+    else
+      y = 3;
+    for (int i = 0; i < 4; i++)
+      if (m [i])
+        n = i;
+    w = 0;
+  end
+endmodule
+";
+    let config = FormatConfig {
+        wrap_multiline_blocks: false,
+        ..cfg()
+    };
+    let formatted = format_text(input, &config).unwrap();
+    assert_eq!(formatted, expected);
+}
+
+#[test]
+fn indents_continuation_lines() {
+    let input = "module m (
+input logic clk,
+output logic q
+);
+assign q = a &
+b;
+always_ff @(posedge clk)
+q <= f(x,
+y);
+endmodule
+";
+    let expected = "\
+module m(
+  input logic clk,
+  output logic q
+);
+  assign q = a &
+    b;
+  always_ff @(posedge clk)
+    q <= f(x,
+      y);
+endmodule
+";
+    assert_eq!(format_text(input, &cfg()).unwrap(), expected);
+
+    let aligned = FormatConfig {
+        continuation_indent: 2,
+        align_to_open_paren: true,
+        ..cfg()
+    };
+    let expected = "\
+module m(
+    input logic clk,
+    output logic q
+);
+  assign q = a &
+      b;
+  always_ff @(posedge clk)
+    q <= f(x,
+           y);
+endmodule
+";
+    assert_eq!(format_text(input, &aligned).unwrap(), expected);
+}
+
+#[test]
+fn inactive_branches_do_not_leave_a_statement_open() {
+    let input = "module m;
+`ifdef FOO
+assign a = b;
+`else
+assign a = c;
+`endif
+assign q = ~clk;
+endmodule
+";
+    let expected = "\
+module m;
+`ifdef FOO
+assign a = b;
+`else
+  assign a = c;
+`endif
+  assign q = ~clk;
+endmodule
+";
+    assert_eq!(format_text(input, &cfg()).unwrap(), expected);
+}
+
+#[test]
+fn lays_out_port_lists_one_per_line() {
+    let input = "module m(input logic clk, // clock
+input rst_n,
+// data
+output logic [7:0] q, output wire [3:0] r [2]);
+endmodule
+";
+    let config = FormatConfig {
+        port_list_per_line: true,
+        ..cfg()
+    };
+    let expected = "\
+module m(
+  input  logic         clk,      // clock
+  input                rst_n,
+  // data
+  output logic [7 : 0] q,
+  output wire  [3 : 0] r     [2]
+);
+endmodule
+";
+    assert_eq!(format_text(input, &config).unwrap(), expected);
+
+    let config = FormatConfig {
+        port_list_leading_commas: true,
+        align_port_list: false,
+        ..config
+    };
+    let expected = "\
+module m(
+    input logic clk // clock
+  , input rst_n
+  // data
+  , output logic [7 : 0] q
+  , output wire [3 : 0] r [2]
+);
+endmodule
+";
+    assert_eq!(format_text(input, &config).unwrap(), expected);
+}
+
+#[test]
+fn lays_out_parameter_lists_and_declarations() {
+    let input = "module m #(parameter int WIDTH = 8, parameter type T = logic, localparam DEPTH = 16) (input clk);
+parameter int A = 1;
+localparam logic [3:0] LONG_NAME = 4; // note
+localparam B = 2;
+localparam X = 1, Y = 2;
+endmodule
+";
+    let config = FormatConfig {
+        param_list_max_entries: 2,
+        ..cfg()
+    };
+    let expected = "\
+module m # (
+  parameter  int  WIDTH = 8,
+  parameter  type T     = logic,
+  localparam      DEPTH = 16
+) (input clk);
+  parameter  int           A         = 1;
+  localparam logic [3 : 0] LONG_NAME = 4; // note
+  localparam               B         = 2;
+  localparam X = 1, Y = 2;
+endmodule
+";
+    assert_eq!(format_text(input, &config).unwrap(), expected);
+
+    // Lists that fit on their line are left alone by default, however many entries they have.
+    let config = FormatConfig {
+        align_parameters: false,
+        ..cfg()
+    };
+    let expected = "\
+module m # (parameter int WIDTH = 8, parameter type T = logic, localparam DEPTH = 16) (input clk);
+  parameter int A = 1;
+  localparam logic [3 : 0] LONG_NAME = 4; // note
+  localparam B = 2;
+  localparam X = 1, Y = 2;
+endmodule
+";
+    assert_eq!(format_text(input, &config).unwrap(), expected);
+}
+
+#[test]
+fn lays_out_instance_connections_one_per_line() {
+    let input = "module top;
+sub #(.WIDTH(8), .DEPTH(DEPTH)) u_sub (.clk(clk), // clock
+.rst_n  (rst_n),
+.data,
+.*);
+sub u_ordered(a, b);
+endmodule
+";
+    let config = FormatConfig {
+        connections_per_line: true,
+        ..cfg()
+    };
+    let expected = "\
+module top;
+  sub # (
+    .WIDTH (8),
+    .DEPTH (DEPTH)
+  ) u_sub(
+    .clk   (clk),   // clock
+    .rst_n (rst_n),
+    .data,
+    .*
+  );
+  sub u_ordered(a, b);
+endmodule
+";
+    assert_eq!(format_text(input, &config).unwrap(), expected);
+
+    let config = FormatConfig {
+        align_connections: false,
+        ..config
+    };
+    let formatted = format_text(input, &config).unwrap();
+    assert!(
+        formatted.contains("    .clk(clk), // clock\n    .rst_n(rst_n),\n"),
+        "{formatted}"
+    );
+}
+
+#[test]
+fn aligns_consecutive_assignments() {
+    let input = "module m;
+assign a = b;
+assign long_name = c; // note
+assign p = q, r = s;
+always_ff @(posedge clk) begin
+q <= d;
+count <= count + 1;
+// reset value
+x += 2;
+
+y = 3;
+if (c) z = 1;
+end
+endmodule
+";
+    let config = FormatConfig {
+        align_assignments: true,
+        ..cfg()
+    };
+    let expected = "\
+module m;
+  assign a         = b;
+  assign long_name = c; // note
+  assign p = q, r = s;
+  always_ff @(posedge clk) begin
+    q     <= d;
+    count <= count + 1;
+    // reset value
+    x += 2;
+    y = 3;
+    if (c) z = 1;
+  end
+endmodule
+";
+    assert_eq!(format_text(input, &config).unwrap(), expected);
+    assert_eq!(format_text(expected, &config).unwrap(), expected);
+}
+
+#[test]
+fn aligns_assignments_at_the_start_of_a_block() {
+    // sv-parser reads `x = 0;` before the first statement as a declaration without a type.
+    let input = "module m;
+initial begin
+x = 0;
+longer_name = 1;
+foo();
+end
+endmodule
+";
+    let config = FormatConfig {
+        align_assignments: true,
+        ..cfg()
+    };
+    let expected = "\
+module m;
+  initial begin
+    x           = 0;
+    longer_name = 1;
+    foo();
+  end
+endmodule
+";
+    assert_eq!(format_text(input, &config).unwrap(), expected);
+    assert_eq!(format_text(expected, &config).unwrap(), expected);
+}
+
+#[test]
+fn aligns_assignments_across_removed_blank_lines() {
+    let input = "module m;
+always_comb begin
+c += 3;
+dd <= 4;
+
+yyyyy = 2;
+end
+endmodule
+";
+    let config = FormatConfig {
+        align_assignments: true,
+        ..cfg()
+    };
+    let expected = "\
+module m;
+  always_comb begin
+    c     += 3;
+    dd    <= 4;
+    yyyyy = 2;
+  end
+endmodule
+";
+    let once = format_text(input, &config).unwrap();
+    assert_eq!(once, expected);
+    assert_eq!(format_text(&once, &config).unwrap(), once);
+}
+
+#[test]
+fn aligns_declaration_columns() {
+    let input = "module m;
+logic [7:0] foo; // byte
+wire bar;
+int unsigned baz = 0; // counter
+logic mem [4];
+logic [DATA_WIDTH*DEPTH-1:0] wide_outlier;
+logic a;
+logic [1:0] b;
+logic c, d;
+endmodule
+";
+    let config = FormatConfig {
+        align_declarations: true,
+        ..cfg()
+    };
+    let expected = "\
+module m;
+  logic        [7 : 0] foo;         // byte
+  wire                 bar;
+  int unsigned         baz     = 0; // counter
+  logic                mem [4];
+  logic [DATA_WIDTH * DEPTH - 1 : 0] wide_outlier;
+  logic         a;
+  logic [1 : 0] b;
+  logic c, d;
+endmodule
+";
+    assert_eq!(format_text(input, &config).unwrap(), expected);
+    assert_eq!(format_text(expected, &config).unwrap(), expected);
+
+    let config = FormatConfig {
+        declaration_max_padding: 4,
+        ..config
+    };
+    let formatted = format_text(input, &config).unwrap();
+    assert!(
+        formatted.contains("  wire bar;\n  int unsigned baz = 0; // counter\n"),
+        "{formatted}"
+    );
+}
+
+#[test]
+fn aligns_declarations_across_removed_blank_lines() {
+    let input = "module m;
+logic a;
+int unsigned bbbb;
+
+wire [3:0] c;
+assign x=1;
+
+assign yyyyy=2;
+endmodule
+";
+    let config = FormatConfig {
+        align_declarations: true,
+        ..cfg()
+    };
+    let expected = "\
+module m;
+  logic                a;
+  int unsigned         bbbb;
+  wire         [3 : 0] c;
+  assign x = 1;
+  assign yyyyy = 2;
+endmodule
+";
+    let once = format_text(input, &config).unwrap();
+    assert_eq!(once, expected);
+    assert_eq!(format_text(&once, &config).unwrap(), once);
+}
+
+#[test]
+fn does_not_align_assignments_as_declarations() {
+    let input = "module m;
+initial begin
+x = 0;
+longer_name = 1;
+foo();
+end
+endmodule
+";
+    let config = FormatConfig {
+        align_declarations: true,
+        ..cfg()
+    };
+    let formatted = format_text(input, &config).unwrap();
+    assert!(formatted.contains("    x = 0;\n    longer_name = 1;\n"), "{formatted}");
+}
+
+#[test]
+fn aligns_trailing_comments() {
+    let input = "module m;
+logic a; // first
+logic [7:0] bytes; // second
+assign a = 1; // third
+// own line
+wire x; // alone
+wire y; // fits
+assign y = some_long_signal_name; // past the limit
+endmodule
+";
+    let config = FormatConfig {
+        align_trailing_comments: true,
+        max_line_length: 43,
+        ..cfg()
+    };
+    let expected = "\
+module m;
+  logic a;             // first
+  logic [7 : 0] bytes; // second
+  assign a = 1;        // third
+  // own line
+  wire x; // alone
+  wire y;                           // fits
+  assign y = some_long_signal_name; // past the limit
+endmodule
+";
+    assert_eq!(format_text(input, &config).unwrap(), expected);
+    assert_eq!(format_text(expected, &config).unwrap(), expected);
+
+    let config = FormatConfig {
+        snap_trailing_comments: true,
+        indent_width: 4,
+        max_line_length: 0,
+        ..config
+    };
+    let formatted = format_text(input, &config).unwrap();
+    assert!(
+        formatted.contains("    logic a;                // first\n    logic [7 : 0] bytes;    // second\n"),
+        "{formatted}"
+    );
+}